@@ -1,19 +1,79 @@
-use std::marker::PhantomData;
+use std::{
+    f64::consts::{PI, TAU},
+    marker::PhantomData,
+};
 
 use rand::{
     distributions::{DistIter, Uniform},
-    prelude::{Distribution, ThreadRng},
+    prelude::Distribution,
+    SeedableRng,
 };
+use rand_chacha::ChaCha8Rng;
 
-use crate::wav;
+use crate::{
+    combinators::{Gain, Mix, Multiply, Offset},
+    modulation::Param,
+    wav,
+};
 
+/// Anything that produces audio.
+///
+/// Instruments work in floating point, where full scale is between -1 and 1.
+/// Samples are only converted into a [`wav::AudioSample`] type by the
+/// iterators, so that instruments can be combined without losing precision or
+/// clipping midway. The trait is object safe, so instruments can be boxed and
+/// picked at runtime.
 pub trait Instrument {
-    /// Gets the current audio sample in a single channel.
-    fn get_sample_mono<T: wav::AudioSample>(&mut self, time: f64) -> Option<T>;
+    /// Tells the instrument how many samples per second it will be asked for.
+    /// This is called when an iterator over the instrument is created.
+    fn set_sample_rate(&mut self, _sample_rate: u32) {}
+
+    /// Gets the current audio sample in a single channel, or `None` once the
+    /// instrument has stopped.
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64>;
+
+    /// Writes the current audio sample for each channel into `out`, or
+    /// returns `None` once the instrument has stopped.
+    ///
+    /// By default, the mono sample is copied into every channel. Instruments
+    /// that place sound in specific channels, such as
+    /// [`Pan`](crate::stereo::Pan), override this, and their mono sample is
+    /// then the sum of the channels they'd write in a frame. Effects like
+    /// filters and envelopes process each channel of the frame the same way
+    /// they process the mono sample. Either way, an instrument should only be
+    /// asked for one of the two each sample.
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        out.fill(self.get_sample_mono(time)?);
+        Some(())
+    }
 
     /// Gets the current audio sample in all channels.
-    fn get_sample<T: wav::AudioSample, const N: usize>(&mut self, time: f64) -> Option<[T; N]> {
-        Some([self.get_sample_mono(time)?; N])
+    fn get_sample<const N: usize>(&mut self, time: f64) -> Option<[f64; N]>
+    where
+        Self: Sized,
+    {
+        let mut frame = [0.0; N];
+        self.get_frame(time, &mut frame)?;
+        Some(frame)
+    }
+
+    /// Fills a block of mono samples, and returns how many were written. This
+    /// is less than the length of the block only if the instrument stopped.
+    ///
+    /// The sample rate from the context is passed on to
+    /// [`Instrument::set_sample_rate`] before the block is processed. By
+    /// default, this then calls [`Instrument::get_sample_mono`] for each
+    /// sample, but instruments can override it to process whole blocks at
+    /// once.
+    fn process_block(&mut self, out: &mut [f32], ctx: &ProcessContext) -> usize {
+        self.set_sample_rate(ctx.sample_rate);
+        for (i, x) in out.iter_mut().enumerate() {
+            match self.get_sample_mono(ctx.time(i)) {
+                Some(sample) => *x = sample as f32,
+                None => return i,
+            }
+        }
+        out.len()
     }
 
     fn iter_mono<'a, T: wav::AudioSample>(
@@ -35,6 +95,107 @@ pub trait Instrument {
     {
         InstrumentIter::new(self, sample_rate)
     }
+
+    /// Adds the output of another instrument to this one.
+    fn mix<I: Instrument>(self, other: I) -> Mix<(Self, I)>
+    where
+        Self: Sized,
+    {
+        Mix::new((self, other))
+    }
+
+    /// Scales the output of this instrument.
+    fn gain(self, gain: impl Into<Param>) -> Gain<Self>
+    where
+        Self: Sized,
+    {
+        Gain::new(self, gain)
+    }
+
+    /// Multiplies the output of this instrument by that of another one.
+    fn ring_mod<I: Instrument>(self, other: I) -> Multiply<Self, I>
+    where
+        Self: Sized,
+    {
+        Multiply::new(self, other)
+    }
+
+    /// Adds a value to the output of this instrument.
+    fn offset(self, offset: impl Into<Param>) -> Offset<Self>
+    where
+        Self: Sized,
+    {
+        Offset::new(self, offset)
+    }
+}
+
+impl<I: Instrument + ?Sized> Instrument for Box<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        (**self).set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        (**self).get_sample_mono(time)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        (**self).get_frame(time, out)
+    }
+
+    fn process_block(&mut self, out: &mut [f32], ctx: &ProcessContext) -> usize {
+        (**self).process_block(out, ctx)
+    }
+}
+
+impl<I: Instrument + ?Sized> Instrument for &mut I {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        (**self).set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        (**self).get_sample_mono(time)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        (**self).get_frame(time, out)
+    }
+
+    fn process_block(&mut self, out: &mut [f32], ctx: &ProcessContext) -> usize {
+        (**self).process_block(out, ctx)
+    }
+}
+
+/// Where a block passed to [`Instrument::process_block`] sits in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProcessContext {
+    /// Number of samples per second.
+    pub sample_rate: u32,
+
+    /// The index of the first sample in the block, counting from the start of
+    /// the render.
+    pub start_sample: u64,
+}
+
+impl ProcessContext {
+    pub fn new(sample_rate: u32, start_sample: u64) -> Self {
+        Self {
+            sample_rate,
+            start_sample,
+        }
+    }
+
+    /// The time in seconds of a sample within the block.
+    ///
+    /// This is computed from the sample index rather than by adding up the
+    /// length of each sample, so it doesn't drift over long renders.
+    pub fn time(&self, index: usize) -> f64 {
+        (self.start_sample + index as u64) as f64 / self.sample_rate as f64
+    }
+
+    /// The context for the block right after this one.
+    pub fn next(&self, len: usize) -> Self {
+        Self::new(self.sample_rate, self.start_sample + len as u64)
+    }
 }
 
 /// An iterator that continuously gets mono samples from an instrument.
@@ -43,21 +204,42 @@ pub trait Instrument {
 /// producing samples. Use `take` if you want a fixed amount of samples.
 pub struct InstrumentIterMono<'a, T, U> {
     instrument: &'a mut U,
-    time: f64,
-    tick: f64,
+
+    /// The time the iterator started at, in seconds.
+    start: f64,
+
+    /// How many samples have been produced so far. The time is computed from
+    /// this, so that it doesn't drift over long renders.
+    sample: u64,
+
+    /// Number of samples per second.
+    sample_rate: u32,
+
     _phantom: PhantomData<T>,
 }
 
 impl<'a, T: wav::AudioSample, U: Instrument> InstrumentIterMono<'a, T, U> {
     pub fn new_with_time(instrument: &'a mut U, time: f64, sample_rate: u32) -> Self {
+        instrument.set_sample_rate(sample_rate);
         Self {
             instrument,
-            time,
-            tick: 1.0 / sample_rate as f64,
+            start: time,
+            sample: 0,
+            sample_rate,
             _phantom: PhantomData,
         }
     }
 
+    /// Returns the time of the current sample, and moves on to the next one.
+    ///
+    /// The first sample is at the start time, like the first sample of a
+    /// [`ProcessContext`].
+    fn tick(&mut self) -> f64 {
+        let time = self.start + self.sample as f64 / self.sample_rate as f64;
+        self.sample += 1;
+        time
+    }
+
     pub fn new(instrument: &'a mut U, sample_rate: u32) -> Self {
         Self::new_with_time(instrument, 0.0, sample_rate)
     }
@@ -67,8 +249,8 @@ impl<'a, T: wav::AudioSample, U: Instrument> Iterator for InstrumentIterMono<'a,
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.time += self.tick;
-        self.instrument.get_sample_mono(self.time)
+        let time = self.tick();
+        self.instrument.get_sample_mono(time).map(T::from_f64)
     }
 }
 
@@ -98,69 +280,751 @@ impl<'a, T: wav::AudioSample, U: Instrument, const N: usize> Iterator
     type Item = [T; N];
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.time += self.0.tick;
-        self.0.instrument.get_sample(self.0.time)
+        let time = self.0.tick();
+        let frame: [f64; N] = self.0.instrument.get_sample(time)?;
+        Some(frame.map(T::from_f64))
+    }
+}
+
+/// A phase accumulator, the shared core of every oscillator.
+///
+/// Rather than computing the phase from the current time, the phase advances
+/// by `freq / sample_rate` each sample. This way, the frequency can change
+/// from one sample to the next without the wave jumping.
+#[derive(Clone, Copy, Debug)]
+pub struct Phase {
+    /// The position within the current period, between 0 and 1.
+    phase: f64,
+
+    /// Number of samples per second.
+    sample_rate: u32,
+}
+
+impl Default for Phase {
+    fn default() -> Self {
+        Self {
+            phase: 0.0,
+            sample_rate: crate::DEFAULT_SAMPLE_RATE,
+        }
+    }
+}
+
+impl Phase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of samples per second.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate
+    }
+
+    /// Number of samples per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// How much the phase advances each sample at the given frequency.
+    pub fn increment(&self, freq: f64) -> f64 {
+        freq / self.sample_rate as f64
+    }
+
+    /// Returns the current phase, then advances it by one sample at the given
+    /// frequency.
+    pub fn advance(&mut self, freq: f64) -> f64 {
+        let phase = self.phase;
+        self.phase = (self.phase + self.increment(freq)).rem_euclid(1.0);
+        phase
+    }
+}
+
+/// The PolyBLEP correction for a unit step at phase 0, for a wave at the given
+/// phase whose phase increases by `dt` each sample.
+///
+/// Subtracting this from a wave that jumps down by 2 at phase 0 smooths out
+/// the discontinuity, which removes most of the aliasing.
+fn poly_blep(phase: f64, dt: f64) -> f64 {
+    if phase < dt {
+        let x = phase / dt;
+        2.0 * x - x * x - 1.0
+    } else if phase > 1.0 - dt {
+        let x = (phase - 1.0) / dt;
+        x * x + 2.0 * x + 1.0
+    } else {
+        0.0
     }
 }
 
 /// Represents a basic square wave.
 pub struct Square {
     /// The frequency of the wave in Hertz.
-    freq: f64,
+    freq: Param,
+
+    /// Whether to smooth out the jumps in the wave to prevent aliasing.
+    band_limited: bool,
+
+    /// The position within the current period.
+    phase: Phase,
 }
 
 impl Default for Square {
     fn default() -> Self {
-        Self { freq: 440.0 }
+        Self::new(440.0)
     }
 }
 
 impl Square {
-    pub fn new(freq: f64) -> Self {
-        Self { freq }
+    pub fn new(freq: impl Into<Param>) -> Self {
+        Self {
+            freq: freq.into(),
+            band_limited: false,
+            phase: Phase::new(),
+        }
+    }
+
+    /// A square wave with PolyBLEP anti-aliasing.
+    pub fn band_limited(freq: impl Into<Param>) -> Self {
+        Self {
+            band_limited: true,
+            ..Self::new(freq)
+        }
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
     }
 }
 
 impl Instrument for Square {
-    fn get_sample_mono<T: wav::AudioSample>(&mut self, time: f64) -> Option<T> {
-        Some(if time * self.freq % 1.0 < 0.5 {
-            T::MIN
-        } else {
-            T::MAX
-        })
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let freq = self.freq.value(time);
+        let dt = self.phase.increment(freq);
+        let phase = self.phase.advance(freq);
+        if !self.band_limited {
+            return Some(if phase < 0.5 { -1.0 } else { 1.0 });
+        }
+
+        // The wave jumps down at phase 0 and up at phase 1/2.
+        let mut x = if phase < 0.5 { -1.0 } else { 1.0 };
+        x -= poly_blep(phase, dt);
+        x += poly_blep((phase + 0.5) % 1.0, dt);
+        Some(x)
     }
 }
 
 /// Represents a basic saw wave.
 pub struct Saw {
     /// The frequency of the wave in Hertz.
-    freq: f64,
+    freq: Param,
+
+    /// Whether to smooth out the jumps in the wave to prevent aliasing.
+    band_limited: bool,
+
+    /// The position within the current period.
+    phase: Phase,
 }
 
 impl Default for Saw {
     fn default() -> Self {
-        Self { freq: 440.0 }
+        Self::new(440.0)
     }
 }
 
 impl Saw {
-    pub fn new(freq: f64) -> Self {
-        Self { freq }
+    pub fn new(freq: impl Into<Param>) -> Self {
+        Self {
+            freq: freq.into(),
+            band_limited: false,
+            phase: Phase::new(),
+        }
+    }
+
+    /// A saw wave with PolyBLEP anti-aliasing.
+    pub fn band_limited(freq: impl Into<Param>) -> Self {
+        Self {
+            band_limited: true,
+            ..Self::new(freq)
+        }
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
     }
 }
 
 impl Instrument for Saw {
-    fn get_sample_mono<T: wav::AudioSample>(&mut self, time: f64) -> Option<T> {
-        Some(T::from_f64(time * self.freq % 1.0))
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let freq = self.freq.value(time);
+        let dt = self.phase.increment(freq);
+        let phase = self.phase.advance(freq);
+        if !self.band_limited {
+            return Some(2.0 * phase - 1.0);
+        }
+
+        let x = 2.0 * phase - 1.0 - poly_blep(phase, dt);
+        Some(x)
+    }
+}
+
+/// Represents a basic sine wave.
+pub struct Sine {
+    /// The frequency of the wave in Hertz.
+    freq: Param,
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl Default for Sine {
+    fn default() -> Self {
+        Self::new(440.0)
+    }
+}
+
+impl Sine {
+    pub fn new(freq: impl Into<Param>) -> Self {
+        Self {
+            freq: freq.into(),
+            phase: Phase::new(),
+        }
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+}
+
+impl Instrument for Sine {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let phase = self.phase.advance(self.freq.value(time));
+        Some((TAU * phase).sin())
+    }
+}
+
+/// Represents a basic triangle wave.
+pub struct Triangle {
+    /// The frequency of the wave in Hertz.
+    freq: Param,
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl Default for Triangle {
+    fn default() -> Self {
+        Self::new(440.0)
+    }
+}
+
+impl Triangle {
+    pub fn new(freq: impl Into<Param>) -> Self {
+        Self {
+            freq: freq.into(),
+            phase: Phase::new(),
+        }
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+}
+
+impl Instrument for Triangle {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let phase = self.phase.advance(self.freq.value(time));
+        Some(1.0 - 2.0 * (2.0 * phase - 1.0).abs())
+    }
+}
+
+/// Represents a pulse wave with a variable duty cycle.
+///
+/// Each period starts low and ends high, like a [`Square`], so that a pulse
+/// with a width of 1/2 is in phase with a square wave.
+pub struct Pulse {
+    /// The frequency of the wave in Hertz.
+    freq: Param,
+
+    /// The fraction of each period in which the wave is high, between 0 and 1.
+    width: Param,
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl Default for Pulse {
+    fn default() -> Self {
+        Self::new(440.0)
+    }
+}
+
+impl Pulse {
+    pub fn new(freq: impl Into<Param>) -> Self {
+        Self::new_with_width(freq, 0.5)
+    }
+
+    pub fn new_with_width(freq: impl Into<Param>, width: impl Into<Param>) -> Self {
+        Self {
+            freq: freq.into(),
+            width: width.into(),
+            phase: Phase::new(),
+        }
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+
+    /// Sets the fraction of each period in which the wave is high.
+    pub fn set_width(&mut self, width: impl Into<Param>) {
+        self.width = width.into()
+    }
+}
+
+impl Instrument for Pulse {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.width.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let width = self.width.value(time);
+        Some(if self.phase.advance(self.freq.value(time)) < 1.0 - width {
+            -1.0
+        } else {
+            1.0
+        })
+    }
+}
+
+/// How a [`Wavetable`] reads values between the entries of its tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Draws a line between the two closest entries.
+    Linear,
+
+    /// Fits a Catmull-Rom spline through the four closest entries.
+    Cubic,
+}
+
+/// A single-cycle table, along with progressively low-passed copies of it.
+///
+/// Level `l` only keeps the harmonics up to `len / 2 >> l`, so that higher
+/// notes can be played from a level with no harmonics above Nyquist.
+struct Mipmap {
+    levels: Vec<Vec<f64>>,
+}
+
+impl Mipmap {
+    fn new(table: &[f64]) -> Self {
+        let len = table.len();
+        let mut spectrum: Vec<_> = table.iter().map(|&x| (x, 0.0)).collect();
+        fourier(&mut spectrum, false);
+
+        // Each level is rebuilt by adding up only the harmonics it keeps, as
+        // an inverse transform of the whole spectrum would be quadratic for
+        // tables whose length isn't a power of two. The table is real, so the
+        // negative frequencies just double the real part of the positive ones.
+        let twiddles = twiddles(len);
+        let mut levels = vec![table.to_vec()];
+        let mut max_harmonic = len / 2;
+        while max_harmonic > 1 {
+            max_harmonic /= 2;
+            let level = (0..len)
+                .map(|n| {
+                    let mut x = spectrum[0].0;
+                    for (k, &(re, im)) in (1..=max_harmonic).zip(&spectrum[1..]) {
+                        let (sin, cos) = twiddles[k * n % len];
+                        x += 2.0 * (re * cos - im * sin);
+                    }
+                    x / len as f64
+                })
+                .collect();
+            levels.push(level);
+        }
+
+        Self { levels }
+    }
+
+    /// Reads the table at the given phase, from the first level with no
+    /// harmonics above `max_harmonic`.
+    fn sample(&self, phase: f64, max_harmonic: f64, interpolation: Interpolation) -> f64 {
+        let table = &self.levels[0];
+        let len = table.len();
+        let level = (0..self.levels.len())
+            .find(|&l| ((len / 2) >> l) as f64 <= max_harmonic)
+            .unwrap_or(self.levels.len() - 1);
+        let table = &self.levels[level];
+
+        let pos = phase * len as f64;
+        let i = pos as usize % len;
+        let t = pos.fract();
+        let at = |offset: usize| table[(i + offset) % len];
+
+        match interpolation {
+            Interpolation::Linear => at(0) + t * (at(1) - at(0)),
+            Interpolation::Cubic => {
+                let (y0, y1, y2, y3) = (at(len - 1), at(0), at(1), at(2));
+                y1 + 0.5
+                    * t
+                    * (y2 - y0
+                        + t * (2.0 * y0 - 5.0 * y1 + 4.0 * y2 - y3
+                            + t * (3.0 * (y1 - y2) + y3 - y0)))
+            }
+        }
+    }
+}
+
+/// The sine and cosine of each of the `len` evenly spaced angles around the
+/// circle.
+fn twiddles(len: usize) -> Vec<(f64, f64)> {
+    (0..len)
+        .map(|i| (TAU * i as f64 / len as f64).sin_cos())
+        .collect()
+}
+
+/// Computes the discrete Fourier transform of a sequence of complex numbers,
+/// as `(re, im)` pairs, in place. The inverse transform isn't normalized.
+///
+/// This uses a radix-2 FFT when the length is a power of two, and the naive
+/// quadratic algorithm otherwise.
+fn fourier(buf: &mut [(f64, f64)], inverse: bool) {
+    let len = buf.len();
+    let sign = if inverse { 1.0 } else { -1.0 };
+
+    if !len.is_power_of_two() {
+        let twiddles = twiddles(len);
+        let input = buf.to_vec();
+        for (k, out) in buf.iter_mut().enumerate() {
+            *out = input
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (n, &(a, b))| {
+                    let (sin, cos) = twiddles[k * n % len];
+                    let sin = sign * sin;
+                    (re + a * cos - b * sin, im + a * sin + b * cos)
+                });
+        }
+        return;
+    }
+
+    // Sorts the entries in bit-reversed order.
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= len {
+        let angle = sign * TAU / size as f64;
+        for start in (0..len).step_by(size) {
+            for k in 0..size / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = buf[start + k];
+                let (c, d) = buf[start + k + size / 2];
+                let (re, im) = (c * cos - d * sin, c * sin + d * cos);
+                buf[start + k] = (a + re, b + im);
+                buf[start + k + size / 2] = (a - re, b - im);
+            }
+        }
+        size *= 2;
+    }
+}
+
+/// Plays back arbitrary single-cycle waveforms.
+///
+/// The wavetable can hold several frames, and the morph position crossfades
+/// between them. Each frame is mipmapped, so that high notes don't alias.
+pub struct Wavetable {
+    /// The frequency of the wave in Hertz.
+    freq: Param,
+
+    /// The single-cycle tables to morph between.
+    frames: Vec<Mipmap>,
+
+    /// The position between the first and last frames, between 0 and 1.
+    morph: Param,
+
+    /// How to read values between table entries.
+    interpolation: Interpolation,
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl Wavetable {
+    /// A wavetable playing a single table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is empty.
+    pub fn new(freq: impl Into<Param>, table: Vec<f64>) -> Self {
+        Self::new_with_frames(freq, vec![table])
+    }
+
+    /// A wavetable that morphs between several tables. These can have
+    /// different lengths.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no tables or any of them is empty.
+    pub fn new_with_frames(freq: impl Into<Param>, frames: Vec<Vec<f64>>) -> Self {
+        assert!(
+            !frames.is_empty() && frames.iter().all(|frame| !frame.is_empty()),
+            "wavetables can't be empty"
+        );
+
+        Self {
+            freq: freq.into(),
+            frames: frames.iter().map(|frame| Mipmap::new(frame)).collect(),
+            morph: Param::Constant(0.0),
+            interpolation: Interpolation::Linear,
+            phase: Phase::new(),
+        }
+    }
+
+    /// A wavetable made from consecutive frames of `frame_len` samples in some
+    /// audio data. Channels are mixed down to mono, and any leftover samples
+    /// at the end are ignored.
+    ///
+    /// Returns `None` if `frame_len` is 0, or if the audio is shorter than a
+    /// single frame.
+    pub fn from_audio<T: wav::AudioSample, const N: usize>(
+        freq: impl Into<Param>,
+        audio: &wav::AudioData<T, N>,
+        frame_len: usize,
+    ) -> Option<Self> {
+        if frame_len == 0 || audio.data().len() < frame_len {
+            return None;
+        }
+
+        let mono: Vec<f64> = audio
+            .data()
+            .iter()
+            .map(|frame| frame.iter().map(|x| x.to_f64()).sum::<f64>() / N as f64)
+            .collect();
+
+        let frames = mono.chunks_exact(frame_len).map(<[f64]>::to_vec).collect();
+        Some(Self::new_with_frames(freq, frames))
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+
+    /// Sets the position between the first and last frames, between 0 and 1.
+    pub fn set_morph(&mut self, morph: impl Into<Param>) {
+        self.morph = morph.into()
+    }
+
+    /// Sets how to read values between table entries.
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation
+    }
+}
+
+impl Instrument for Wavetable {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.morph.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let freq = self.freq.value(time);
+        let morph = self.morph.value(time).clamp(0.0, 1.0);
+        let max_harmonic = self.phase.sample_rate() as f64 / (2.0 * freq.abs());
+        let phase = self.phase.advance(freq);
+        let sample = |frame: &Mipmap| frame.sample(phase, max_harmonic, self.interpolation);
+
+        let pos = morph * (self.frames.len() - 1) as f64;
+        let i = pos as usize;
+        let mut x = sample(&self.frames[i]);
+        if let Some(next) = self.frames.get(i + 1) {
+            x += pos.fract() * (sample(next) - x);
+        }
+        Some(x)
+    }
+}
+
+/// A single sine wave within an [`Additive`] instrument.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Partial {
+    /// The frequency of the partial, relative to the note being played.
+    pub ratio: f64,
+
+    /// The amplitude of the partial, where 1 is full scale.
+    pub amplitude: f64,
+
+    /// The phase the partial starts at, between 0 and 1.
+    pub phase: f64,
+}
+
+impl Partial {
+    pub fn new(ratio: f64, amplitude: f64) -> Self {
+        Self {
+            ratio,
+            amplitude,
+            phase: 0.0,
+        }
+    }
+}
+
+/// Sums any number of sine partials.
+///
+/// Partials above the Nyquist frequency are skipped, so the output never
+/// aliases.
+pub struct Additive {
+    /// The frequency of the note in Hertz.
+    freq: Param,
+
+    /// The sine waves to add up.
+    partials: Vec<Partial>,
+
+    /// The position within the current period of each partial.
+    phases: Vec<Phase>,
+}
+
+impl Additive {
+    pub fn new(freq: impl Into<Param>, partials: Vec<Partial>) -> Self {
+        Self {
+            freq: freq.into(),
+            phases: vec![Phase::new(); partials.len()],
+            partials,
+        }
+    }
+
+    /// The first `count` harmonics of a saw wave.
+    pub fn saw(freq: impl Into<Param>, count: usize) -> Self {
+        let partials = (1..=count)
+            .map(|n| Partial {
+                ratio: n as f64,
+                amplitude: 2.0 / (PI * n as f64),
+                // Flips the sine so that the wave rises, like `Saw`.
+                phase: 0.5,
+            })
+            .collect();
+        Self::new(freq, partials)
+    }
+
+    /// The first `count` odd harmonics of a square wave.
+    pub fn square(freq: impl Into<Param>, count: usize) -> Self {
+        let partials = (0..count)
+            .map(|n| Partial {
+                ratio: (2 * n + 1) as f64,
+                amplitude: 4.0 / (PI * (2 * n + 1) as f64),
+                // Flips the sine so that the wave starts low, like `Square`.
+                phase: 0.5,
+            })
+            .collect();
+        Self::new(freq, partials)
+    }
+
+    /// A tonewheel organ with the given drawbar settings, from 0 (off) to 8
+    /// (loudest), in the usual order: 16', 5 1/3', 8', 4', 2 2/3', 2', 1 3/5',
+    /// 1 1/3' and 1'.
+    pub fn organ(freq: impl Into<Param>, drawbars: [u8; 9]) -> Self {
+        const RATIOS: [f64; 9] = [0.5, 1.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0];
+
+        // Each drawbar step is 3 dB.
+        let amplitudes = drawbars.map(|level| match level {
+            0 => 0.0,
+            _ => 10f64.powf(-3.0 * (8 - level.min(8)) as f64 / 20.0),
+        });
+        let total: f64 = amplitudes.iter().sum();
+
+        let partials = RATIOS
+            .iter()
+            .zip(amplitudes)
+            .filter(|(_, amplitude)| *amplitude > 0.0)
+            .map(|(&ratio, amplitude)| Partial::new(ratio, amplitude / total))
+            .collect();
+        Self::new(freq, partials)
+    }
+
+    /// Changes the frequency, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+
+    /// The sine waves being added up.
+    pub fn partials(&self) -> &[Partial] {
+        &self.partials
+    }
+
+    /// Changes the amplitude of a partial.
+    pub fn set_amplitude(&mut self, index: usize, amplitude: f64) {
+        self.partials[index].amplitude = amplitude
+    }
+}
+
+impl Instrument for Additive {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        for phase in &mut self.phases {
+            phase.set_sample_rate(sample_rate)
+        }
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let note = self.freq.value(time);
+        let mut x = 0.0;
+        for (partial, phase) in self.partials.iter().zip(&mut self.phases) {
+            let freq = note * partial.ratio;
+            let nyquist = phase.sample_rate() as f64 / 2.0;
+            let current = phase.advance(freq);
+            if freq.abs() < nyquist {
+                x += partial.amplitude * (TAU * (current + partial.phase)).sin();
+            }
+        }
+        Some(x)
     }
 }
 
 /// Represents white noise.
-pub struct Random(DistIter<Uniform<f64>, ThreadRng, f64>);
+///
+/// Noise made with [`Random::seeded`] is the same on every render. It comes
+/// from ChaCha8, whose output is fixed for a given seed, rather than `StdRng`,
+/// which may change between versions of `rand`.
+pub struct Random(DistIter<Uniform<f64>, ChaCha8Rng, f64>);
 
 impl Default for Random {
     fn default() -> Self {
-        Self(Uniform::new(0.0, 1.0).sample_iter(rand::thread_rng()))
+        Self::from_rng(ChaCha8Rng::from_entropy())
     }
 }
 
@@ -168,10 +1032,286 @@ impl Random {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// White noise that always comes out the same for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn from_rng(rng: ChaCha8Rng) -> Self {
+        Self(Uniform::new_inclusive(-1.0, 1.0).sample_iter(rng))
+    }
+
+    /// Gets the next white noise value, between -1 and 1.
+    fn next_value(&mut self) -> f64 {
+        // The iterator never ends.
+        self.0.next().unwrap()
+    }
 }
 
 impl Instrument for Random {
-    fn get_sample_mono<T: wav::AudioSample>(&mut self, _: f64) -> Option<T> {
-        Some(T::from_f64(self.0.next()?))
+    fn get_sample_mono(&mut self, _: f64) -> Option<f64> {
+        Some(self.next_value())
+    }
+}
+
+/// Represents pink noise, whose power falls by 3 dB per octave.
+///
+/// This uses Paul Kellet's refined filter over white noise, which is accurate
+/// to within 0.05 dB above 9.2 Hz at 44.1 kHz.
+pub struct Pink {
+    /// The white noise being filtered.
+    white: Random,
+
+    /// The state of each of the filter's poles.
+    poles: [f64; 7],
+}
+
+impl Default for Pink {
+    fn default() -> Self {
+        Self::from_white(Random::new())
+    }
+}
+
+impl Pink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pink noise that always comes out the same for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_white(Random::seeded(seed))
+    }
+
+    fn from_white(white: Random) -> Self {
+        Self {
+            white,
+            poles: [0.0; 7],
+        }
+    }
+
+    /// Gets the next pink noise value, roughly between -1 and 1.
+    fn next_value(&mut self) -> f64 {
+        let white = self.white.next_value();
+        let b = &mut self.poles;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b.iter().sum::<f64>() + white * 0.5362;
+        b[6] = white * 0.115926;
+
+        // Brings the output roughly back to unit gain.
+        pink * 0.11
+    }
+}
+
+impl Instrument for Pink {
+    fn get_sample_mono(&mut self, _: f64) -> Option<f64> {
+        Some(self.next_value())
+    }
+}
+
+/// Represents brown (or red) noise, whose power falls by 6 dB per octave.
+///
+/// This integrates white noise with a slight leak, so that it doesn't drift
+/// away.
+pub struct Brown {
+    /// The white noise being filtered.
+    white: Random,
+
+    /// The integrated value.
+    value: f64,
+}
+
+impl Default for Brown {
+    fn default() -> Self {
+        Self::from_white(Random::new())
+    }
+}
+
+impl Brown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Brown noise that always comes out the same for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_white(Random::seeded(seed))
+    }
+
+    fn from_white(white: Random) -> Self {
+        Self { white, value: 0.0 }
+    }
+}
+
+impl Instrument for Brown {
+    fn get_sample_mono(&mut self, _: f64) -> Option<f64> {
+        self.value = (self.value + 0.02 * self.white.next_value()) / 1.02;
+        Some(3.5 * self.value)
+    }
+}
+
+/// Represents blue noise, whose power rises by 3 dB per octave.
+///
+/// This differentiates pink noise.
+pub struct Blue {
+    /// The pink noise being filtered.
+    pink: Pink,
+
+    /// The last pink noise value.
+    prev: f64,
+}
+
+impl Default for Blue {
+    fn default() -> Self {
+        Self::from_pink(Pink::new())
+    }
+}
+
+impl Blue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blue noise that always comes out the same for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_pink(Pink::seeded(seed))
+    }
+
+    fn from_pink(pink: Pink) -> Self {
+        Self { pink, prev: 0.0 }
+    }
+}
+
+impl Instrument for Blue {
+    fn get_sample_mono(&mut self, _: f64) -> Option<f64> {
+        let pink = self.pink.next_value();
+        let x = pink - self.prev;
+        self.prev = pink;
+        // Brings the output roughly back to unit gain.
+        Some(3.0 * x)
+    }
+}
+
+/// Represents violet noise, whose power rises by 6 dB per octave.
+///
+/// This differentiates white noise.
+pub struct Violet {
+    /// The white noise being filtered.
+    white: Random,
+
+    /// The last white noise value.
+    prev: f64,
+}
+
+impl Default for Violet {
+    fn default() -> Self {
+        Self::from_white(Random::new())
+    }
+}
+
+impl Violet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Violet noise that always comes out the same for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_white(Random::seeded(seed))
+    }
+
+    fn from_white(white: Random) -> Self {
+        Self { white, prev: 0.0 }
+    }
+}
+
+impl Instrument for Violet {
+    fn get_sample_mono(&mut self, _: f64) -> Option<f64> {
+        let white = self.white.next_value();
+        let x = (white - self.prev) / 2.0;
+        self.prev = white;
+        Some(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fraction of the power of a render that lies outside the harmonics
+    /// of its frequency, which is all aliasing for these oscillators.
+    ///
+    /// The render lasts exactly 0.1 s, so every component falls on a bin of
+    /// the transform and there's no leakage between bins.
+    fn alias_ratio(instrument: &mut impl Instrument, freq: f64) -> f64 {
+        const SAMPLE_RATE: u32 = 44100;
+        let len = SAMPLE_RATE as usize / 10;
+
+        let mut buf: Vec<_> = instrument
+            .iter_mono::<f64>(SAMPLE_RATE)
+            .take(len)
+            .map(|x| (x, 0.0))
+            .collect();
+        fourier(&mut buf, false);
+
+        let fundamental = (freq / 10.0).round() as usize;
+        let (mut alias, mut total) = (0.0, 0.0);
+        for (bin, (re, im)) in buf.iter().enumerate().take(len / 2).skip(1) {
+            let power = re * re + im * im;
+            total += power;
+            if bin % fundamental != 0 {
+                alias += power;
+            }
+        }
+        alias / total
+    }
+
+    #[test]
+    fn wavetable_from_short_audio() {
+        let mut audio = wav::AudioData::<i16, 1>::new(44100);
+        audio.extend_data([[0], [1000], [0], [-1000]].into_iter());
+        assert!(Wavetable::from_audio(440.0, &audio, 0).is_none());
+        assert!(Wavetable::from_audio(440.0, &audio, 5).is_none());
+        assert!(Wavetable::from_audio(440.0, &audio, 4).is_some());
+    }
+
+    #[test]
+    fn pulse_matches_square() {
+        let pulse: Vec<f64> = Pulse::new(441.0).iter_mono(44100).take(300).collect();
+        let square: Vec<f64> = Square::new(441.0).iter_mono(44100).take(300).collect();
+        assert_eq!(pulse, square);
+    }
+
+    // 3010 Hz doesn't divide 44.1 kHz evenly, so the aliases land between the
+    // harmonics instead of on top of them.
+    const FREQ: f64 = 3010.0;
+
+    #[test]
+    fn band_limited_saw_aliases_less() {
+        let naive = alias_ratio(&mut Saw::new(FREQ), FREQ);
+        let band_limited = alias_ratio(&mut Saw::band_limited(FREQ), FREQ);
+        assert!(naive > 0.05, "naive saw alias ratio: {}", naive);
+        assert!(
+            band_limited < naive / 20.0,
+            "alias ratio: {} naive, {} band-limited",
+            naive,
+            band_limited
+        );
+    }
+
+    #[test]
+    fn band_limited_square_aliases_less() {
+        let naive = alias_ratio(&mut Square::new(FREQ), FREQ);
+        let band_limited = alias_ratio(&mut Square::band_limited(FREQ), FREQ);
+        assert!(naive > 0.03, "naive square alias ratio: {}", naive);
+        assert!(
+            band_limited < naive / 20.0,
+            "alias ratio: {} naive, {} band-limited",
+            naive,
+            band_limited
+        );
     }
 }
@@ -0,0 +1,256 @@
+use crate::{
+    basic::{Instrument, ProcessContext},
+    modulation::Param,
+};
+
+/// Scales the output of an instrument. Modulating the gain with an LFO gives
+/// tremolo.
+pub struct Gain<I: Instrument> {
+    /// The instrument being scaled.
+    inner: I,
+
+    /// The factor the output is multiplied by.
+    gain: Param,
+}
+
+impl<I: Instrument> Gain<I> {
+    pub fn new(inner: I, gain: impl Into<Param>) -> Self {
+        Self {
+            inner,
+            gain: gain.into(),
+        }
+    }
+
+    /// Changes the factor the output is multiplied by.
+    pub fn set_gain(&mut self, gain: impl Into<Param>) {
+        self.gain = gain.into()
+    }
+}
+
+impl<I: Instrument> Instrument for Gain<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate);
+        self.gain.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let gain = self.gain.value(time);
+        Some(gain * self.inner.get_sample_mono(time)?)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let gain = self.gain.value(time);
+        self.inner.get_frame(time, out)?;
+        for x in out {
+            *x *= gain
+        }
+        Some(())
+    }
+
+    fn process_block(&mut self, out: &mut [f32], ctx: &ProcessContext) -> usize {
+        self.gain.set_sample_rate(ctx.sample_rate);
+        let len = self.inner.process_block(out, ctx);
+        for (i, x) in out[..len].iter_mut().enumerate() {
+            *x *= self.gain.value(ctx.time(i)) as f32;
+        }
+        len
+    }
+}
+
+/// Adds up the outputs of several instruments.
+///
+/// This works on a pair of instruments, as built by [`Instrument::mix`], or on
+/// a whole `Vec` of them. Instruments that have stopped count as silence, and
+/// the mix only stops once all of them have.
+pub struct Mix<S> {
+    /// The instruments being mixed.
+    sources: S,
+
+    /// A buffer for the frames of each instrument.
+    scratch: Vec<f64>,
+}
+
+impl<S> Mix<S> {
+    pub fn new(sources: S) -> Self {
+        Self {
+            sources,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Gets the instruments being mixed.
+    pub fn sources_mut(&mut self) -> &mut S {
+        &mut self.sources
+    }
+}
+
+impl<A: Instrument, B: Instrument> Instrument for Mix<(A, B)> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sources.0.set_sample_rate(sample_rate);
+        self.sources.1.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let a = self.sources.0.get_sample_mono(time);
+        let b = self.sources.1.get_sample_mono(time);
+        if a.is_none() && b.is_none() {
+            return None;
+        }
+
+        Some(a.unwrap_or(0.0) + b.unwrap_or(0.0))
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        self.scratch.resize(out.len(), 0.0);
+        let a = self.sources.0.get_frame(time, out);
+        let b = self.sources.1.get_frame(time, &mut self.scratch);
+        match (a, b) {
+            (None, None) => return None,
+            (None, Some(())) => out.copy_from_slice(&self.scratch),
+            (Some(()), None) => {}
+            (Some(()), Some(())) => {
+                for (x, y) in out.iter_mut().zip(&self.scratch) {
+                    *x += y
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+impl<I: Instrument> Instrument for Mix<Vec<I>> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        for source in &mut self.sources {
+            source.set_sample_rate(sample_rate)
+        }
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let mut sum = None;
+        for source in &mut self.sources {
+            if let Some(x) = source.get_sample_mono(time) {
+                *sum.get_or_insert(0.0) += x;
+            }
+        }
+        sum
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        self.scratch.resize(out.len(), 0.0);
+        out.fill(0.0);
+        let mut playing = None;
+        for source in &mut self.sources {
+            if source.get_frame(time, &mut self.scratch).is_some() {
+                playing = Some(());
+                for (x, y) in out.iter_mut().zip(&self.scratch) {
+                    *x += y
+                }
+            }
+        }
+        playing
+    }
+}
+
+/// Multiplies the outputs of two instruments. With two oscillators, this is
+/// ring modulation.
+///
+/// The product stops as soon as either instrument does.
+pub struct Multiply<A: Instrument, B: Instrument> {
+    /// The first factor.
+    a: A,
+
+    /// The second factor.
+    b: B,
+
+    /// A buffer for the frames of the second factor.
+    scratch: Vec<f64>,
+}
+
+impl<A: Instrument, B: Instrument> Multiply<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            scratch: Vec::new(),
+        }
+    }
+}
+
+impl<A: Instrument, B: Instrument> Instrument for Multiply<A, B> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.a.set_sample_rate(sample_rate);
+        self.b.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        // Both instruments are asked for a sample, so that they stay in step.
+        let a = self.a.get_sample_mono(time);
+        let b = self.b.get_sample_mono(time);
+        Some(a? * b?)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        self.scratch.resize(out.len(), 0.0);
+        let a = self.a.get_frame(time, out);
+        let b = self.b.get_frame(time, &mut self.scratch);
+        a?;
+        b?;
+        for (x, y) in out.iter_mut().zip(&self.scratch) {
+            *x *= y
+        }
+        Some(())
+    }
+}
+
+/// Adds a constant, or modulated, value to the output of an instrument.
+pub struct Offset<I: Instrument> {
+    /// The instrument being shifted.
+    inner: I,
+
+    /// The value added to the output.
+    offset: Param,
+}
+
+impl<I: Instrument> Offset<I> {
+    pub fn new(inner: I, offset: impl Into<Param>) -> Self {
+        Self {
+            inner,
+            offset: offset.into(),
+        }
+    }
+
+    /// Changes the value added to the output.
+    pub fn set_offset(&mut self, offset: impl Into<Param>) {
+        self.offset = offset.into()
+    }
+}
+
+impl<I: Instrument> Instrument for Offset<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate);
+        self.offset.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let offset = self.offset.value(time);
+        Some(self.inner.get_sample_mono(time)? + offset)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let offset = self.offset.value(time);
+        self.inner.get_frame(time, out)?;
+        for x in out {
+            *x += offset
+        }
+        Some(())
+    }
+
+    fn process_block(&mut self, out: &mut [f32], ctx: &ProcessContext) -> usize {
+        self.offset.set_sample_rate(ctx.sample_rate);
+        let len = self.inner.process_block(out, ctx);
+        for (i, x) in out[..len].iter_mut().enumerate() {
+            *x += self.offset.value(ctx.time(i)) as f32;
+        }
+        len
+    }
+}
@@ -0,0 +1,338 @@
+use crate::basic::Instrument;
+
+/// The shape of the segments of an envelope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// Straight lines between levels.
+    Linear,
+
+    /// Exponential approaches, like the RC circuits of analog envelopes. These
+    /// sound more natural, especially in the decay and release.
+    Exponential,
+}
+
+/// The segment an envelope is currently in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// The envelope is at zero, waiting for the gate to open.
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// How close an exponential attack gets to its target, as a fraction of the
+/// distance. Aiming past the peak makes the attack reach it in finite time.
+const ATTACK_RATIO: f64 = 0.3;
+
+/// How close an exponential decay or release gets to its target, as a fraction
+/// of the distance.
+const DECAY_RATIO: f64 = 0.0001;
+
+/// An attack-decay-sustain-release envelope, with values between 0 and 1.
+///
+/// Opening the gate starts the attack, which rises to 1 and then decays to the
+/// sustain level. Closing the gate starts the release, which falls to 0.
+#[derive(Clone, Copy, Debug)]
+pub struct Adsr {
+    /// The time to rise from 0 to 1, in seconds.
+    attack: f64,
+
+    /// The time to fall from 1 to the sustain level, in seconds.
+    decay: f64,
+
+    /// The level held while the gate is open, between 0 and 1.
+    sustain: f64,
+
+    /// The time to fall from the sustain level to 0, in seconds.
+    release: f64,
+
+    /// The shape of each segment.
+    curve: Curve,
+
+    /// Whether opening the gate while the envelope is active restarts it from
+    /// 0, rather than from the current level.
+    retrigger: bool,
+
+    /// The segment the envelope is currently in.
+    stage: Stage,
+
+    /// The current output.
+    level: f64,
+
+    /// The level at which the release started.
+    release_level: f64,
+
+    /// Number of samples per second.
+    sample_rate: u32,
+}
+
+impl Adsr {
+    pub fn new(attack: f64, decay: f64, sustain: f64, release: f64) -> Self {
+        Self {
+            attack,
+            decay,
+            sustain: sustain.clamp(0.0, 1.0),
+            release,
+            curve: Curve::Linear,
+            retrigger: false,
+            stage: Stage::Idle,
+            level: 0.0,
+            release_level: 0.0,
+            sample_rate: crate::DEFAULT_SAMPLE_RATE,
+        }
+    }
+
+    /// Sets the shape of each segment.
+    pub fn set_curve(&mut self, curve: Curve) {
+        self.curve = curve
+    }
+
+    /// Sets whether opening the gate while the envelope is active restarts it
+    /// from 0, rather than from the current level.
+    pub fn set_retrigger(&mut self, retrigger: bool) {
+        self.retrigger = retrigger
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate
+    }
+
+    /// The segment the envelope is currently in.
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// Whether the envelope is producing anything, i.e. whether it hasn't
+    /// finished its release.
+    pub fn is_active(&self) -> bool {
+        self.stage != Stage::Idle
+    }
+
+    /// Opens the gate, starting the attack.
+    pub fn gate_on(&mut self) {
+        if self.retrigger {
+            self.level = 0.0;
+        }
+        self.stage = Stage::Attack;
+    }
+
+    /// Closes the gate, starting the release.
+    pub fn gate_off(&mut self) {
+        if self.is_active() {
+            self.stage = Stage::Release;
+            self.release_level = self.level;
+        }
+    }
+
+    /// Moves the level towards `target` for a segment that takes `time`
+    /// seconds to cover `span`. Returns whether it got there.
+    fn approach(&mut self, target: f64, span: f64, time: f64, ratio: f64) -> bool {
+        let samples = time * self.sample_rate as f64;
+        if samples < 1.0 {
+            self.level = target;
+            return true;
+        }
+
+        let rising = target > self.level;
+        match self.curve {
+            Curve::Linear => {
+                let step = span / samples;
+                self.level += if rising { step } else { -step };
+            }
+            Curve::Exponential => {
+                let coef = (-((1.0 + ratio) / ratio).ln() / samples).exp();
+                let overshoot = if rising { ratio } else { -ratio } * span;
+                self.level = (target + overshoot) * (1.0 - coef) + self.level * coef;
+            }
+        }
+
+        let arrived = if rising {
+            self.level >= target
+        } else {
+            self.level <= target
+        };
+        if arrived {
+            self.level = target;
+        }
+        arrived
+    }
+
+    /// Returns the current level, then advances the envelope by one sample.
+    pub fn next_level(&mut self) -> f64 {
+        let level = self.level;
+        match self.stage {
+            Stage::Idle | Stage::Sustain => {}
+            Stage::Attack => {
+                if self.approach(1.0, 1.0, self.attack, ATTACK_RATIO) {
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                let span = 1.0 - self.sustain;
+                if self.approach(self.sustain, span, self.decay, DECAY_RATIO) {
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Release => {
+                let span = self.release_level;
+                if self.approach(0.0, span, self.release, DECAY_RATIO) {
+                    self.stage = Stage::Idle;
+                }
+            }
+        }
+        level
+    }
+}
+
+/// An envelope whose gate opens on the first sample, and optionally closes by
+/// itself after some time.
+#[derive(Clone, Copy, Debug)]
+pub struct GatedAdsr {
+    /// The envelope being gated.
+    envelope: Adsr,
+
+    /// How long the gate stays open, in seconds, if it closes by itself. This
+    /// is cleared once the gate closes.
+    gate_length: Option<f64>,
+
+    /// The time of the first sample.
+    start: Option<f64>,
+}
+
+impl GatedAdsr {
+    /// An envelope whose gate stays open until [`GatedAdsr::gate_off`] is
+    /// called.
+    pub fn new(envelope: Adsr) -> Self {
+        Self {
+            envelope,
+            gate_length: None,
+            start: None,
+        }
+    }
+
+    /// An envelope whose gate closes after the given number of seconds, like
+    /// a note of that length.
+    pub fn new_with_gate(envelope: Adsr, gate_length: f64) -> Self {
+        Self {
+            gate_length: Some(gate_length),
+            ..Self::new(envelope)
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.envelope.set_sample_rate(sample_rate)
+    }
+
+    /// Opens the gate again, starting a new attack.
+    pub fn gate_on(&mut self) {
+        self.envelope.gate_on()
+    }
+
+    /// Closes the gate, starting the release.
+    pub fn gate_off(&mut self) {
+        self.envelope.gate_off()
+    }
+
+    /// The envelope being gated.
+    pub fn envelope_mut(&mut self) -> &mut Adsr {
+        &mut self.envelope
+    }
+
+    /// Opens or closes the gate as needed, then returns the current level and
+    /// advances the envelope. Returns `None` once the release has finished.
+    pub fn next_level(&mut self, time: f64) -> Option<f64> {
+        let start = *self.start.get_or_insert_with(|| {
+            self.envelope.gate_on();
+            time
+        });
+
+        if let Some(length) = self.gate_length {
+            if time - start >= length {
+                self.gate_length = None;
+                self.envelope.gate_off();
+            }
+        }
+
+        if !self.envelope.is_active() {
+            return None;
+        }
+
+        Some(self.envelope.next_level())
+    }
+}
+
+/// Shapes the amplitude of an instrument with an envelope.
+///
+/// The gate opens on the first sample. Once it's closed and the release has
+/// finished, the instrument stops producing samples, so any iterator over it
+/// ends.
+pub struct Enveloped<I: Instrument> {
+    /// The instrument being shaped.
+    inner: I,
+
+    /// The envelope shaping the instrument.
+    envelope: GatedAdsr,
+}
+
+impl<I: Instrument> Enveloped<I> {
+    /// Shapes an instrument with an envelope. The gate stays open until
+    /// [`Enveloped::gate_off`] is called.
+    pub fn new(inner: I, envelope: Adsr) -> Self {
+        Self {
+            inner,
+            envelope: GatedAdsr::new(envelope),
+        }
+    }
+
+    /// Shapes an instrument with an envelope whose gate closes after the
+    /// given number of seconds, like a note of that length.
+    pub fn new_with_gate(inner: I, envelope: Adsr, gate_length: f64) -> Self {
+        Self {
+            inner,
+            envelope: GatedAdsr::new_with_gate(envelope, gate_length),
+        }
+    }
+
+    /// Opens the gate again, starting a new attack.
+    pub fn gate_on(&mut self) {
+        self.envelope.gate_on()
+    }
+
+    /// Closes the gate, starting the release.
+    pub fn gate_off(&mut self) {
+        self.envelope.gate_off()
+    }
+
+    /// The instrument being shaped.
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// The envelope shaping the instrument.
+    pub fn envelope_mut(&mut self) -> &mut Adsr {
+        self.envelope.envelope_mut()
+    }
+}
+
+impl<I: Instrument> Instrument for Enveloped<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate);
+        self.envelope.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let level = self.envelope.next_level(time)?;
+        Some(level * self.inner.get_sample_mono(time)?)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let level = self.envelope.next_level(time)?;
+        self.inner.get_frame(time, out)?;
+        for x in out {
+            *x *= level
+        }
+        Some(())
+    }
+}
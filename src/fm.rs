@@ -0,0 +1,291 @@
+use std::f64::consts::TAU;
+
+use crate::{
+    basic::{Instrument, Phase},
+    modulation::Param,
+};
+
+/// A sine carrier whose phase is modulated by another instrument.
+///
+/// This is a mono instrument. The modulator is read as a mono sample, so a
+/// stereo modulator is mixed down, and the output is copied into every
+/// channel of a frame.
+pub struct PhaseMod<M: Instrument> {
+    /// The frequency of the carrier in Hertz.
+    freq: Param,
+
+    /// How far the modulator pushes the phase around, in radians at full
+    /// scale.
+    index: Param,
+
+    /// The instrument driving the phase of the carrier.
+    modulator: M,
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl<M: Instrument> PhaseMod<M> {
+    pub fn new(freq: impl Into<Param>, index: impl Into<Param>, modulator: M) -> Self {
+        Self {
+            freq: freq.into(),
+            index: index.into(),
+            modulator,
+            phase: Phase::new(),
+        }
+    }
+
+    /// Changes the frequency of the carrier, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+
+    /// Sets the modulation index, in radians at full scale.
+    pub fn set_index(&mut self, index: impl Into<Param>) {
+        self.index = index.into()
+    }
+}
+
+impl<M: Instrument> Instrument for PhaseMod<M> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        self.index.set_sample_rate(sample_rate);
+        self.phase.set_sample_rate(sample_rate);
+        self.modulator.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let index = self.index.value(time);
+        let modulation = index * self.modulator.get_sample_mono(time)?;
+        let phase = self.phase.advance(self.freq.value(time));
+        Some((TAU * phase + modulation).sin())
+    }
+}
+
+/// A sine oscillator used as a building block of an FM algorithm.
+#[derive(Clone, Copy, Debug)]
+pub struct Operator {
+    /// The frequency of the operator, relative to the note being played.
+    ratio: f64,
+
+    /// The output level. When the operator modulates another one, this is the
+    /// modulation index in radians.
+    index: f64,
+
+    /// How much the operator modulates its own phase, in radians.
+    feedback: f64,
+
+    /// The last two outputs. Feedback uses their average, which keeps high
+    /// amounts of feedback from turning into noise.
+    prev: [f64; 2],
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl Operator {
+    pub fn new(ratio: f64, index: f64) -> Self {
+        Self::new_with_feedback(ratio, index, 0.0)
+    }
+
+    pub fn new_with_feedback(ratio: f64, index: f64, feedback: f64) -> Self {
+        Self {
+            ratio,
+            index,
+            feedback,
+            prev: [0.0; 2],
+            phase: Phase::new(),
+        }
+    }
+
+    /// Sets the frequency of the operator, relative to the note being played.
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = ratio
+    }
+
+    /// Sets the output level, or modulation index.
+    pub fn set_index(&mut self, index: f64) {
+        self.index = index
+    }
+
+    /// Sets how much the operator modulates its own phase, in radians.
+    pub fn set_feedback(&mut self, feedback: f64) {
+        self.feedback = feedback
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    /// Computes the next output for a note of the given frequency, with the
+    /// phase pushed around by `modulation` radians.
+    pub fn next(&mut self, freq: f64, modulation: f64) -> f64 {
+        let feedback = self.feedback * (self.prev[0] + self.prev[1]) / 2.0;
+        let phase = self.phase.advance(freq * self.ratio);
+        let out = self.index * (TAU * phase + modulation + feedback).sin();
+        self.prev = [out, self.prev[0]];
+        out
+    }
+}
+
+/// Describes how the four operators of an [`Fm4`] are connected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Algorithm {
+    /// `modulation[i][j]` is how much operator `j` modulates operator `i`.
+    ///
+    /// Operators are evaluated from last to first, so operators can only
+    /// modulate lower operators within the same sample. Any other connection
+    /// uses the output from the previous sample.
+    modulation: [[f64; 4]; 4],
+
+    /// How much each operator contributes to the output.
+    carriers: [f64; 4],
+}
+
+impl Algorithm {
+    pub fn new(modulation: [[f64; 4]; 4], carriers: [f64; 4]) -> Self {
+        Self {
+            modulation,
+            carriers,
+        }
+    }
+
+    /// Builds an algorithm from a list of `(modulator, carrier)` connections
+    /// and a list of operators that are heard.
+    fn from_connections(connections: &[(usize, usize)], carriers: &[usize]) -> Self {
+        let mut algorithm = Self::new([[0.0; 4]; 4], [0.0; 4]);
+        for &(modulator, carrier) in connections {
+            algorithm.modulation[carrier][modulator] = 1.0;
+        }
+        for &carrier in carriers {
+            // Keeps the sum of the carriers in range.
+            algorithm.carriers[carrier] = 1.0 / carriers.len() as f64;
+        }
+        algorithm
+    }
+
+    /// One of the eight classic 4-operator algorithms, numbered from 1 to 8
+    /// as on the DX21 and TX81Z. Operators are numbered from 0 here, so
+    /// operator 1 of the synth is operator 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number isn't between 1 and 8.
+    pub fn dx(number: u8) -> Self {
+        match number {
+            // 3 → 2 → 1 → 0
+            1 => Self::from_connections(&[(3, 2), (2, 1), (1, 0)], &[0]),
+            // 3 → 1, 2 → 1, 1 → 0
+            2 => Self::from_connections(&[(3, 1), (2, 1), (1, 0)], &[0]),
+            // 2 → 1 → 0, 3 → 0
+            3 => Self::from_connections(&[(2, 1), (1, 0), (3, 0)], &[0]),
+            // 3 → 2 → 0, 1 → 0
+            4 => Self::from_connections(&[(3, 2), (2, 0), (1, 0)], &[0]),
+            // 1 → 0, 3 → 2
+            5 => Self::from_connections(&[(1, 0), (3, 2)], &[0, 2]),
+            // 3 → 0, 3 → 1, 3 → 2
+            6 => Self::from_connections(&[(3, 0), (3, 1), (3, 2)], &[0, 1, 2]),
+            // 3 → 2
+            7 => Self::from_connections(&[(3, 2)], &[0, 1, 2]),
+            // No modulation at all.
+            8 => Self::from_connections(&[], &[0, 1, 2, 3]),
+            _ => panic!("there's no algorithm {}", number),
+        }
+    }
+}
+
+/// A 4-operator FM synth, DX style.
+///
+/// This is a mono instrument, whose output is copied into every channel of a
+/// frame.
+pub struct Fm4 {
+    /// The frequency of the note in Hertz.
+    freq: Param,
+
+    /// The operators, which are connected according to the algorithm.
+    operators: [Operator; 4],
+
+    /// How the operators are connected.
+    algorithm: Algorithm,
+
+    /// The latest output of each operator.
+    outputs: [f64; 4],
+}
+
+impl Fm4 {
+    pub fn new(freq: impl Into<Param>, operators: [Operator; 4], algorithm: Algorithm) -> Self {
+        Self {
+            freq: freq.into(),
+            operators,
+            algorithm,
+            outputs: [0.0; 4],
+        }
+    }
+
+    /// A bright, inharmonic bell.
+    pub fn bell(freq: impl Into<Param>) -> Self {
+        Self::new(
+            freq,
+            [
+                Operator::new(1.0, 1.0),
+                Operator::new(3.5, 2.5),
+                Operator::new(1.0, 1.0),
+                Operator::new_with_feedback(1.41, 1.2, 0.3),
+            ],
+            Algorithm::dx(5),
+        )
+    }
+
+    /// A Rhodes-style electric piano, with a metallic tine over a soft body.
+    pub fn electric_piano(freq: impl Into<Param>) -> Self {
+        Self::new(
+            freq,
+            [
+                Operator::new(1.0, 1.0),
+                Operator::new(14.0, 0.4),
+                Operator::new(1.0, 1.0),
+                Operator::new_with_feedback(1.0, 0.8, 0.2),
+            ],
+            Algorithm::dx(5),
+        )
+    }
+
+    /// Changes the frequency of the note, starting from the next sample.
+    pub fn set_freq(&mut self, freq: impl Into<Param>) {
+        self.freq = freq.into()
+    }
+
+    /// Changes how the operators are connected.
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm
+    }
+
+    /// Gets one of the four operators, to tweak its settings.
+    pub fn operator_mut(&mut self, index: usize) -> &mut Operator {
+        &mut self.operators[index]
+    }
+}
+
+impl Instrument for Fm4 {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.freq.set_sample_rate(sample_rate);
+        for operator in &mut self.operators {
+            operator.set_sample_rate(sample_rate)
+        }
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let freq = self.freq.value(time);
+        for i in (0..4).rev() {
+            let modulation: f64 = (0..4)
+                .map(|j| self.algorithm.modulation[i][j] * self.outputs[j])
+                .sum();
+            self.outputs[i] = self.operators[i].next(freq, modulation);
+        }
+
+        let out = (0..4)
+            .map(|i| self.algorithm.carriers[i] * self.outputs[i])
+            .sum();
+        Some(out)
+    }
+}
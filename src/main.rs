@@ -1,11 +1,17 @@
 #![allow(dead_code)]
 
-use std::path;
+use std::{path, process};
 
 use basic::Instrument;
 
 pub mod basic;
+pub mod combinators;
+pub mod envelope;
+pub mod filters;
+pub mod fm;
+pub mod modulation;
 pub mod scales;
+pub mod stereo;
 pub mod wav;
 
 const DEFAULT_SAMPLE_RATE: u32 = 44100;
@@ -14,8 +20,12 @@ const SAMPLE_RATE: u32 = DEFAULT_SAMPLE_RATE;
 fn main() {
     let mut data = wav::AudioData::<i16, 1>::new(SAMPLE_RATE);
 
-    data.add_data_at(0, basic::Square::default().iter(SAMPLE_RATE).take(44100));
-    data.add_data_at(0, basic::Saw::default().iter(SAMPLE_RATE).take(44100));
-    data.save_to(path::Path::new("D:/Violeta/synthtest.wav"))
-        .unwrap();
+    let mut instrument = basic::Square::default()
+        .mix(basic::Saw::default())
+        .gain(0.5);
+    data.add_data_at(0, instrument.iter(SAMPLE_RATE).take(44100));
+    if let Err(err) = data.save_to(path::Path::new("D:/Violeta/synthtest.wav")) {
+        eprintln!("Could not save the render: {}", err);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,223 @@
+use std::{f64::consts::TAU, fmt};
+
+use crate::{
+    basic::{Instrument, Phase},
+    envelope::{Adsr, GatedAdsr},
+};
+
+/// A source of values that change over time, such as an LFO. These drive the
+/// parameters of instruments.
+pub trait Modulator {
+    /// Tells the modulator how many samples per second it will be asked for.
+    fn set_sample_rate(&mut self, _sample_rate: u32) {}
+
+    /// Gets the current value. This is called once per sample.
+    fn value(&mut self, time: f64) -> f64;
+}
+
+/// A parameter of an instrument, which can either be constant or follow a
+/// modulator.
+///
+/// Any `f64` converts into a constant parameter, and any modulator converts
+/// into a modulated one.
+pub enum Param {
+    Constant(f64),
+    Modulated(Box<dyn Modulator>),
+}
+
+impl Param {
+    /// Tells the modulator, if any, how many samples per second it will be
+    /// asked for.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        if let Self::Modulated(modulator) = self {
+            modulator.set_sample_rate(sample_rate)
+        }
+    }
+
+    /// Gets the current value. Modulated parameters should be read exactly
+    /// once per sample.
+    pub fn value(&mut self, time: f64) -> f64 {
+        match self {
+            Self::Constant(x) => *x,
+            Self::Modulated(modulator) => modulator.value(time),
+        }
+    }
+}
+
+impl fmt::Debug for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constant(x) => f.debug_tuple("Constant").field(x).finish(),
+            Self::Modulated(_) => f.write_str("Modulated"),
+        }
+    }
+}
+
+impl From<f64> for Param {
+    fn from(x: f64) -> Self {
+        Self::Constant(x)
+    }
+}
+
+impl<M: Modulator + 'static> From<M> for Param {
+    fn from(modulator: M) -> Self {
+        Self::Modulated(Box::new(modulator))
+    }
+}
+
+/// The shape of an [`Lfo`]. Every shape goes between -1 and 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+impl Waveform {
+    /// The value of the wave at a given phase, between 0 and 1.
+    pub fn eval(self, phase: f64) -> f64 {
+        match self {
+            Self::Sine => (TAU * phase).sin(),
+            Self::Triangle => 1.0 - 2.0 * (2.0 * phase - 1.0).abs(),
+            Self::Saw => 2.0 * phase - 1.0,
+            Self::Square => {
+                if phase < 0.5 {
+                    -1.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+/// A low-frequency oscillator, which swings around a center value.
+#[derive(Clone, Copy, Debug)]
+pub struct Lfo {
+    waveform: Waveform,
+
+    /// The frequency of the oscillator in Hertz.
+    rate: f64,
+
+    /// How far the value swings from the center.
+    depth: f64,
+
+    /// The value the oscillator swings around.
+    center: f64,
+
+    /// The position within the current period.
+    phase: Phase,
+}
+
+impl Lfo {
+    /// An LFO that swings around 0.
+    pub fn new(waveform: Waveform, rate: f64, depth: f64) -> Self {
+        Self::new_with_center(waveform, rate, depth, 0.0)
+    }
+
+    /// An LFO that swings around a center value, such as the base frequency
+    /// for vibrato.
+    pub fn new_with_center(waveform: Waveform, rate: f64, depth: f64, center: f64) -> Self {
+        Self {
+            waveform,
+            rate,
+            depth,
+            center,
+            phase: Phase::new(),
+        }
+    }
+}
+
+impl Modulator for Lfo {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.phase.set_sample_rate(sample_rate)
+    }
+
+    fn value(&mut self, _: f64) -> f64 {
+        let phase = self.phase.advance(self.rate);
+        self.center + self.depth * self.waveform.eval(phase)
+    }
+}
+
+/// Sweeps a value with an envelope, from `from` when the envelope is at 0 to
+/// `to` when it's at 1.
+///
+/// The gate opens on the first sample.
+#[derive(Clone, Copy, Debug)]
+pub struct EnvelopeMod {
+    envelope: GatedAdsr,
+
+    /// The value when the envelope is at 0.
+    from: f64,
+
+    /// The value when the envelope is at 1.
+    to: f64,
+}
+
+impl EnvelopeMod {
+    /// Sweeps a value with an envelope whose gate stays open.
+    pub fn new(envelope: Adsr, from: f64, to: f64) -> Self {
+        Self {
+            envelope: GatedAdsr::new(envelope),
+            from,
+            to,
+        }
+    }
+
+    /// Sweeps a value with an envelope whose gate closes after the given
+    /// number of seconds.
+    pub fn new_with_gate(envelope: Adsr, from: f64, to: f64, gate_length: f64) -> Self {
+        Self {
+            envelope: GatedAdsr::new_with_gate(envelope, gate_length),
+            from,
+            to,
+        }
+    }
+}
+
+impl Modulator for EnvelopeMod {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.envelope.set_sample_rate(sample_rate)
+    }
+
+    fn value(&mut self, time: f64) -> f64 {
+        // The value stays at `from` once the release has finished.
+        let level = self.envelope.next_level(time).unwrap_or(0.0);
+        self.from + (self.to - self.from) * level
+    }
+}
+
+/// Uses the output of an instrument as a modulator, swinging around a center
+/// value. Once the instrument stops, the value stays at the center.
+pub struct InstrumentMod<I: Instrument> {
+    /// The instrument whose output is used.
+    inner: I,
+
+    /// How far the value swings from the center at full scale.
+    depth: f64,
+
+    /// The value the output swings around.
+    center: f64,
+}
+
+impl<I: Instrument> InstrumentMod<I> {
+    pub fn new(inner: I, depth: f64, center: f64) -> Self {
+        Self {
+            inner,
+            depth,
+            center,
+        }
+    }
+}
+
+impl<I: Instrument> Modulator for InstrumentMod<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate)
+    }
+
+    fn value(&mut self, time: f64) -> f64 {
+        let x = self.inner.get_sample_mono(time).unwrap_or(0.0);
+        self.center + self.depth * x
+    }
+}
@@ -0,0 +1,261 @@
+use std::f64::consts::FRAC_PI_4;
+
+use crate::{basic::Instrument, modulation::Param};
+
+/// How a [`Pan`] splits a mono signal between the left and right channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanLaw {
+    /// Keeps the total power the same at every position, so the center is
+    /// 3 dB down on each side.
+    #[default]
+    ConstantPower,
+
+    /// Keeps the sum of both channels the same at every position, so the
+    /// center is 6 dB down on each side.
+    Linear,
+
+    /// A compromise between the other two, with the center 4.5 dB down on each
+    /// side.
+    Compromise,
+}
+
+impl PanLaw {
+    /// The gains of the left and right channels at a given position, from -1
+    /// (hard left) to 1 (hard right).
+    pub fn gains(self, pan: f64) -> (f64, f64) {
+        let pan = pan.clamp(-1.0, 1.0);
+        let linear = ((1.0 - pan) / 2.0, (1.0 + pan) / 2.0);
+        let (sin, cos) = ((pan + 1.0) * FRAC_PI_4).sin_cos();
+
+        match self {
+            Self::ConstantPower => (cos, sin),
+            Self::Linear => linear,
+            Self::Compromise => ((linear.0 * cos).sqrt(), (linear.1 * sin).sqrt()),
+        }
+    }
+}
+
+/// Places the mono output of an instrument between the left and right
+/// channels.
+///
+/// The first two channels are taken to be left and right, and any others are
+/// left silent. With a single channel, both sides are mixed together, so the
+/// level depends on the pan law.
+pub struct Pan<I: Instrument> {
+    /// The instrument being panned.
+    inner: I,
+
+    /// The position, from -1 (hard left) to 1 (hard right).
+    pan: Param,
+
+    /// How the signal is split between both channels.
+    law: PanLaw,
+}
+
+impl<I: Instrument> Pan<I> {
+    pub fn new(inner: I, pan: impl Into<Param>) -> Self {
+        Self::new_with_law(inner, pan, PanLaw::default())
+    }
+
+    pub fn new_with_law(inner: I, pan: impl Into<Param>, law: PanLaw) -> Self {
+        Self {
+            inner,
+            pan: pan.into(),
+            law,
+        }
+    }
+
+    /// Changes the position, from -1 (hard left) to 1 (hard right).
+    pub fn set_pan(&mut self, pan: impl Into<Param>) {
+        self.pan = pan.into()
+    }
+
+    /// Changes how the signal is split between both channels.
+    pub fn set_law(&mut self, law: PanLaw) {
+        self.law = law
+    }
+}
+
+impl<I: Instrument> Instrument for Pan<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate);
+        self.pan.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let (l, r) = self.law.gains(self.pan.value(time));
+        Some((l + r) * self.inner.get_sample_mono(time)?)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let [left, right, rest @ ..] = out else {
+            out.fill(self.get_sample_mono(time)?);
+            return Some(());
+        };
+
+        let (l, r) = self.law.gains(self.pan.value(time));
+        let x = self.inner.get_sample_mono(time)?;
+        *left = l * x;
+        *right = r * x;
+        rest.fill(0.0);
+        Some(())
+    }
+}
+
+/// Widens or narrows the stereo image of an instrument, by scaling the
+/// difference between its left and right channels.
+///
+/// A width of 0 collapses the image to mono, 1 leaves it alone, and anything
+/// higher exaggerates it. Only the first two channels are affected.
+pub struct Width<I: Instrument> {
+    /// The instrument being processed.
+    inner: I,
+
+    /// How much the side signal is scaled by.
+    width: Param,
+}
+
+impl<I: Instrument> Width<I> {
+    pub fn new(inner: I, width: impl Into<Param>) -> Self {
+        Self {
+            inner,
+            width: width.into(),
+        }
+    }
+
+    /// Changes how much the side signal is scaled by.
+    pub fn set_width(&mut self, width: impl Into<Param>) {
+        self.width = width.into()
+    }
+}
+
+impl<I: Instrument> Instrument for Width<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate);
+        self.width.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        // The mid signal doesn't depend on the width, but it's still read to
+        // keep its modulator in step.
+        self.width.value(time);
+        self.inner.get_sample_mono(time)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let width = self.width.value(time);
+        self.inner.get_frame(time, out)?;
+        if let [left, right, ..] = out {
+            let mid = (*left + *right) / 2.0;
+            let side = width * (*left - *right) / 2.0;
+            *left = mid + side;
+            *right = mid - side;
+        }
+        Some(())
+    }
+}
+
+/// Plays one instrument on the left channel and another on the right.
+///
+/// Any other channels are left silent, and with a single channel, both
+/// instruments are mixed together. This only stops once both instruments have.
+pub struct Stereo<L: Instrument, R: Instrument> {
+    /// The instrument on the left channel.
+    left: L,
+
+    /// The instrument on the right channel.
+    right: R,
+}
+
+impl<L: Instrument, R: Instrument> Stereo<L, R> {
+    pub fn new(left: L, right: R) -> Self {
+        Self { left, right }
+    }
+}
+
+impl<L: Instrument, R: Instrument> Instrument for Stereo<L, R> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.left.set_sample_rate(sample_rate);
+        self.right.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let left = self.left.get_sample_mono(time);
+        let right = self.right.get_sample_mono(time);
+        if left.is_none() && right.is_none() {
+            return None;
+        }
+
+        Some(left.unwrap_or(0.0) + right.unwrap_or(0.0))
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let [left, right, rest @ ..] = out else {
+            out.fill(self.get_sample_mono(time)?);
+            return Some(());
+        };
+
+        let l = self.left.get_sample_mono(time);
+        let r = self.right.get_sample_mono(time);
+        if l.is_none() && r.is_none() {
+            return None;
+        }
+
+        *left = l.unwrap_or(0.0);
+        *right = r.unwrap_or(0.0);
+        rest.fill(0.0);
+        Some(())
+    }
+}
+
+/// Places the mono output of an instrument into any number of channels, each
+/// with its own gain.
+///
+/// This is how a mono instrument is sent to specific speakers of a
+/// multichannel [`AudioData`](crate::wav::AudioData), such as only the center
+/// channel of a 5.1 mix. Channels without a gain are left silent, and the
+/// mono sample is the input scaled by the sum of all gains.
+pub struct Route<I: Instrument> {
+    /// The instrument being routed.
+    inner: I,
+
+    /// The gain of each channel.
+    gains: Vec<f64>,
+}
+
+impl<I: Instrument> Route<I> {
+    pub fn new(inner: I, gains: Vec<f64>) -> Self {
+        Self { inner, gains }
+    }
+
+    /// Sends the instrument to a single channel.
+    pub fn channel(inner: I, channel: usize) -> Self {
+        let mut gains = vec![0.0; channel + 1];
+        gains[channel] = 1.0;
+        Self::new(inner, gains)
+    }
+
+    /// Changes the gain of each channel.
+    pub fn set_gains(&mut self, gains: Vec<f64>) {
+        self.gains = gains
+    }
+}
+
+impl<I: Instrument> Instrument for Route<I> {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.inner.set_sample_rate(sample_rate)
+    }
+
+    fn get_sample_mono(&mut self, time: f64) -> Option<f64> {
+        let gain: f64 = self.gains.iter().sum();
+        Some(gain * self.inner.get_sample_mono(time)?)
+    }
+
+    fn get_frame(&mut self, time: f64, out: &mut [f64]) -> Option<()> {
+        let x = self.inner.get_sample_mono(time)?;
+        for (i, sample) in out.iter_mut().enumerate() {
+            *sample = x * self.gains.get(i).copied().unwrap_or(0.0);
+        }
+        Some(())
+    }
+}
@@ -1,11 +1,178 @@
 use std::{
-    fmt,
+    error, fmt,
     fs::File,
-    io::{self, Write},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
     mem, ops,
     path::Path,
 };
 
+/// The format tag for integer PCM data.
+const WAVE_FORMAT_PCM: u16 = 1;
+
+/// The format tag for floating point data.
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// The format tag for the extensible format, where the actual format is
+/// stored as a GUID in the extension.
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// The last 14 bytes of the `KSDATAFORMAT_SUBTYPE_*` GUIDs. The first two bytes
+/// are the equivalent format tag.
+const SUBTYPE_GUID_SUFFIX: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// The size of a `ds64` chunk without a table.
+const DS64_SIZE: u64 = 28;
+
+/// Speaker positions used in the channel mask of an extensible `fmt ` chunk.
+pub mod speaker {
+    pub const FRONT_LEFT: u32 = 0x1;
+    pub const FRONT_RIGHT: u32 = 0x2;
+    pub const FRONT_CENTER: u32 = 0x4;
+    pub const LOW_FREQUENCY: u32 = 0x8;
+    pub const BACK_LEFT: u32 = 0x10;
+    pub const BACK_RIGHT: u32 = 0x20;
+    pub const SIDE_LEFT: u32 = 0x200;
+    pub const SIDE_RIGHT: u32 = 0x400;
+
+    /// Mono.
+    pub const MONO: u32 = FRONT_CENTER;
+
+    /// Stereo.
+    pub const STEREO: u32 = FRONT_LEFT | FRONT_RIGHT;
+
+    /// Quadraphonic.
+    pub const QUAD: u32 = STEREO | BACK_LEFT | BACK_RIGHT;
+
+    /// 5.1 surround.
+    pub const SURROUND_5_1: u32 = QUAD | FRONT_CENTER | LOW_FREQUENCY;
+
+    /// 7.1 surround.
+    pub const SURROUND_7_1: u32 = SURROUND_5_1 | SIDE_LEFT | SIDE_RIGHT;
+
+    /// The usual speaker layout for a given number of channels, or 0 (no
+    /// particular layout) if there's none.
+    pub const fn default_mask(channels: usize) -> u32 {
+        match channels {
+            1 => MONO,
+            2 => STEREO,
+            4 => QUAD,
+            6 => SURROUND_5_1,
+            8 => SURROUND_7_1,
+            _ => 0,
+        }
+    }
+}
+
+/// An error produced while reading or writing a WAV file.
+#[derive(Debug)]
+pub enum Error {
+    /// An underlying I/O operation failed.
+    Io(io::Error),
+
+    /// The stream does not start with a RIFF/WAVE header.
+    NotWave,
+
+    /// A chunk required to decode the file is missing.
+    MissingChunk([u8; 4]),
+
+    /// A chunk ends before all of its contents could be read.
+    Truncated([u8; 4]),
+
+    /// The audio can't be described within the limits of the format, such as
+    /// a block alignment that doesn't fit in 16 bits.
+    SizeOverflow,
+
+    /// The `fmt ` chunk uses a format tag we can't decode.
+    UnsupportedFormat(u16),
+
+    /// The file holds a different kind of samples than requested, such as
+    /// integer PCM samples when floating point samples were requested. Both
+    /// are given as format tags.
+    FormatMismatch { expected: u16, found: u16 },
+
+    /// The file has a different number of channels than requested.
+    ChannelMismatch { expected: u16, found: u16 },
+
+    /// The file has a different bit depth than requested.
+    BitDepthMismatch { expected: u16, found: u16 },
+
+    /// The file has a different number of bytes per frame than its channels
+    /// and bit depth call for.
+    BlockAlignMismatch { expected: u16, found: u16 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::NotWave => write!(f, "not a RIFF/WAVE stream"),
+            Self::MissingChunk(id) => {
+                write!(f, "missing `{}` chunk", String::from_utf8_lossy(id))
+            }
+            Self::Truncated(id) => {
+                write!(f, "truncated `{}` chunk", String::from_utf8_lossy(id))
+            }
+            Self::SizeOverflow => write!(f, "audio format exceeds the size limits of WAV"),
+            Self::UnsupportedFormat(tag) => write!(f, "unsupported format tag {}", tag),
+            Self::FormatMismatch { expected, found } => write!(
+                f,
+                "expected {} samples, found {} samples",
+                format_name(*expected),
+                format_name(*found)
+            ),
+            Self::ChannelMismatch { expected, found } => {
+                write!(f, "expected {} channels, found {}", expected, found)
+            }
+            Self::BitDepthMismatch { expected, found } => {
+                write!(f, "expected {} bits per sample, found {}", expected, found)
+            }
+            Self::BlockAlignMismatch { expected, found } => {
+                write!(f, "expected {} bytes per frame, found {}", expected, found)
+            }
+        }
+    }
+}
+
+/// A readable name for the formats we can decode.
+fn format_name(tag: u16) -> &'static str {
+    match tag {
+        WAVE_FORMAT_PCM => "integer PCM",
+        WAVE_FORMAT_IEEE_FLOAT => "floating point",
+        _ => "unknown",
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Error {
+    /// Reports running out of data while reading a chunk as the chunk being
+    /// truncated.
+    fn in_chunk(self, id: [u8; 4]) -> Self {
+        match self {
+            Self::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => Self::Truncated(id),
+            err => err,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
 /// One of the allowed primitive types for an audio file. This determines the
 /// bits per sample.
 pub trait AudioSample: fmt::Debug + Copy + ops::Add<Self> + ops::AddAssign<Self> {
@@ -18,11 +185,33 @@ pub trait AudioSample: fmt::Debug + Copy + ops::Add<Self> + ops::AddAssign<Self>
     /// The maximum value for the type.
     const MAX: Self;
 
-    /// Converts a `f64` in the range [0, 1] to this type.
+    /// The format tag written to the `fmt ` chunk for this type.
+    const FORMAT_TAG: u16 = WAVE_FORMAT_PCM;
+
+    /// The number of bytes a sample takes up in the file. This can differ
+    /// from the size of the type in memory.
+    const BYTES: usize = mem::size_of::<Self>();
+
+    /// The number of bits per sample in the file.
+    const BITS: u16 = Self::BYTES as u16 * 8;
+
+    /// Converts a `f64` in the range [-1, 1] to this type, so that -1, 0 and
+    /// 1 map to `MIN`, `ZERO` and `MAX`. Integer types clamp values outside
+    /// of this range.
     fn from_f64(x: f64) -> Self;
 
+    /// Converts this type to a `f64` in the range [-1, 1]. This is the inverse
+    /// of `from_f64`, up to rounding.
+    fn to_f64(self) -> f64;
+
+    /// Adds two samples, clamping at the numeric bounds instead of wrapping.
+    fn saturating_add(self, rhs: Self) -> Self;
+
     /// Converts the given numerical type to little endian.
     fn to_le_bytes(self) -> Vec<u8>;
+
+    /// Reads a sample from its little endian representation.
+    fn from_le_bytes(bytes: &[u8]) -> Self;
 }
 
 impl AudioSample for u8 {
@@ -31,12 +220,25 @@ impl AudioSample for u8 {
     const MAX: u8 = u8::MAX;
 
     fn from_f64(x: f64) -> Self {
-        (Self::MAX as f64 * x) as Self
+        (128.0 + 128.0 * x).round().clamp(0.0, 255.0) as Self
+    }
+
+    fn to_f64(self) -> f64 {
+        (self as f64 - 128.0) / 128.0
+    }
+
+    /// Adds the offsets of both samples from `ZERO`.
+    fn saturating_add(self, rhs: Self) -> Self {
+        (self as i16 + rhs as i16 - 128).clamp(0, 255) as Self
     }
 
     fn to_le_bytes(self) -> Vec<u8> {
         self.to_le_bytes().to_vec()
     }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
 }
 
 impl AudioSample for i16 {
@@ -45,12 +247,26 @@ impl AudioSample for i16 {
     const MAX: i16 = i16::MAX;
 
     fn from_f64(x: f64) -> Self {
-        (Self::MAX as f64 * x) as Self
+        (-(Self::MIN as f64) * x)
+            .round()
+            .clamp(Self::MIN as f64, Self::MAX as f64) as Self
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64 / -(Self::MIN as f64)
+    }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        self.saturating_add(rhs)
     }
 
     fn to_le_bytes(self) -> Vec<u8> {
         self.to_le_bytes().to_vec()
     }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
 }
 
 impl AudioSample for i32 {
@@ -59,12 +275,393 @@ impl AudioSample for i32 {
     const MAX: i32 = i32::MAX;
 
     fn from_f64(x: f64) -> Self {
-        (Self::MAX as f64 * x) as Self
+        (-(Self::MIN as f64) * x)
+            .round()
+            .clamp(Self::MIN as f64, Self::MAX as f64) as Self
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64 / -(Self::MIN as f64)
+    }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        self.saturating_add(rhs)
     }
 
     fn to_le_bytes(self) -> Vec<u8> {
         self.to_le_bytes().to_vec()
     }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+/// A signed 24-bit sample, stored in the low bits of an `i32`.
+///
+/// Arithmetic wraps around on overflow, just like casting between the
+/// primitive integer types does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct I24(i32);
+
+impl I24 {
+    /// The minimum value for the type.
+    pub const MIN: Self = Self(-(1 << 23));
+
+    /// The maximum value for the type.
+    pub const MAX: Self = Self((1 << 23) - 1);
+
+    /// Keeps the lowest 24 bits of an `i32`.
+    pub const fn new(x: i32) -> Self {
+        Self(x << 8 >> 8)
+    }
+
+    /// Converts an `i32` into the nearest 24-bit value.
+    pub fn saturating_new(x: i32) -> Self {
+        Self(x.clamp(Self::MIN.0, Self::MAX.0))
+    }
+
+    /// Returns the value as an `i32`.
+    pub const fn get(self) -> i32 {
+        self.0
+    }
+}
+
+impl ops::Add for I24 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl ops::AddAssign for I24 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs
+    }
+}
+
+impl AudioSample for I24 {
+    const ZERO: I24 = I24(0);
+    const MIN: I24 = I24::MIN;
+    const MAX: I24 = I24::MAX;
+    const BYTES: usize = 3;
+
+    fn from_f64(x: f64) -> Self {
+        Self::saturating_new((-(Self::MIN.0 as f64) * x).round() as i32)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / -(Self::MIN.0 as f64)
+    }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        Self::saturating_new(self.0 + rhs.0)
+    }
+
+    fn to_le_bytes(self) -> Vec<u8> {
+        self.0.to_le_bytes()[..3].to_vec()
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::new(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    }
+}
+
+impl AudioSample for f32 {
+    const ZERO: f32 = 0.0;
+    const MIN: f32 = -1.0;
+    const MAX: f32 = 1.0;
+    const FORMAT_TAG: u16 = WAVE_FORMAT_IEEE_FLOAT;
+
+    fn from_f64(x: f64) -> Self {
+        x as Self
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    /// Floating point samples have plenty of headroom, so this doesn't clamp.
+    fn saturating_add(self, rhs: Self) -> Self {
+        self + rhs
+    }
+
+    fn to_le_bytes(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl AudioSample for f64 {
+    const ZERO: f64 = 0.0;
+    const MIN: f64 = -1.0;
+    const MAX: f64 = 1.0;
+    const FORMAT_TAG: u16 = WAVE_FORMAT_IEEE_FLOAT;
+
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    /// Floating point samples have plenty of headroom, so this doesn't clamp.
+    fn saturating_add(self, rhs: Self) -> Self {
+        self + rhs
+    }
+
+    fn to_le_bytes(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+/// Optional metadata stored in a WAV file alongside the audio.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Text tags from the `LIST/INFO` chunk, such as `INAM` (title) or `ICMT`
+    /// (comments).
+    pub info: Vec<([u8; 4], String)>,
+
+    /// Markers from the `cue ` chunk.
+    pub cues: Vec<Cue>,
+
+    /// Sampler settings from the `smpl` chunk.
+    pub sampler: Option<Sampler>,
+}
+
+/// A marker at a given frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cue {
+    /// A unique identifier for the marker.
+    pub id: u32,
+
+    /// The frame the marker points to.
+    pub position: u32,
+}
+
+/// Settings for playing the audio back in a sampler.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sampler {
+    /// The MIDI note at which the audio plays at its original pitch.
+    pub unity_note: u32,
+
+    /// The fraction of a semitone above the unity note, where `u32::MAX` is
+    /// (almost) a whole semitone.
+    pub pitch_fraction: u32,
+
+    /// The loops within the audio.
+    pub loops: Vec<SampleLoop>,
+}
+
+/// A loop within the audio, used by samplers to sustain a note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SampleLoop {
+    /// A unique identifier for the loop.
+    pub id: u32,
+
+    /// How the loop is played.
+    pub kind: LoopKind,
+
+    /// The first frame of the loop.
+    pub start: u32,
+
+    /// The last frame of the loop, inclusive.
+    pub end: u32,
+
+    /// How many times the loop is played, or 0 for infinitely many.
+    pub play_count: u32,
+}
+
+/// The direction a loop is played in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoopKind {
+    Forward,
+    PingPong,
+    Backward,
+}
+
+impl Metadata {
+    /// Whether there is no metadata to write.
+    pub fn is_empty(&self) -> bool {
+        self.info.is_empty() && self.cues.is_empty() && self.sampler.is_none()
+    }
+
+    /// Gets the value of a text tag.
+    pub fn info(&self, id: [u8; 4]) -> Option<&str> {
+        self.info
+            .iter()
+            .find(|(tag, _)| *tag == id)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a text tag, replacing any previous value.
+    pub fn set_info(&mut self, id: [u8; 4], value: String) {
+        match self.info.iter_mut().find(|(tag, _)| *tag == id) {
+            Some((_, old)) => *old = value,
+            None => self.info.push((id, value)),
+        }
+    }
+
+    /// The contents of the `LIST` chunk, including the `INFO` type.
+    fn list_chunk(&self) -> Vec<u8> {
+        let mut buf = b"INFO".to_vec();
+        for (id, value) in &self.info {
+            // Values are null-terminated.
+            let size = value.len() as u32 + 1;
+            buf.extend_from_slice(id);
+            buf.extend_from_slice(&size.to_le_bytes());
+            buf.extend_from_slice(value.as_bytes());
+            buf.push(0);
+            if size % 2 == 1 {
+                buf.push(0);
+            }
+        }
+        buf
+    }
+
+    /// The contents of the `cue ` chunk.
+    fn cue_chunk(&self) -> Vec<u8> {
+        let mut buf = (self.cues.len() as u32).to_le_bytes().to_vec();
+        for cue in &self.cues {
+            buf.extend_from_slice(&cue.id.to_le_bytes());
+            buf.extend_from_slice(&cue.position.to_le_bytes());
+            buf.extend_from_slice(b"data");
+            // The chunk and block starts are 0 for uncompressed data.
+            buf.extend_from_slice(&[0; 8]);
+            buf.extend_from_slice(&cue.position.to_le_bytes());
+        }
+        buf
+    }
+
+    /// The contents of the `smpl` chunk.
+    fn smpl_chunk(sampler: &Sampler, sample_rate: u32) -> Vec<u8> {
+        // Manufacturer and product.
+        let mut buf = vec![0; 8];
+        let sample_period = 1_000_000_000 / sample_rate.max(1);
+        buf.extend_from_slice(&sample_period.to_le_bytes());
+        buf.extend_from_slice(&sampler.unity_note.to_le_bytes());
+        buf.extend_from_slice(&sampler.pitch_fraction.to_le_bytes());
+        // SMPTE format and offset.
+        buf.extend_from_slice(&[0; 8]);
+        buf.extend_from_slice(&(sampler.loops.len() as u32).to_le_bytes());
+        // Sampler-specific data.
+        buf.extend_from_slice(&[0; 4]);
+
+        for sample_loop in &sampler.loops {
+            let kind: u32 = match sample_loop.kind {
+                LoopKind::Forward => 0,
+                LoopKind::PingPong => 1,
+                LoopKind::Backward => 2,
+            };
+
+            buf.extend_from_slice(&sample_loop.id.to_le_bytes());
+            buf.extend_from_slice(&kind.to_le_bytes());
+            buf.extend_from_slice(&sample_loop.start.to_le_bytes());
+            buf.extend_from_slice(&sample_loop.end.to_le_bytes());
+            // Fraction.
+            buf.extend_from_slice(&[0; 4]);
+            buf.extend_from_slice(&sample_loop.play_count.to_le_bytes());
+        }
+        buf
+    }
+
+    /// Encodes every non-empty metadata chunk, including their headers.
+    fn to_chunks(&self, sample_rate: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut push_chunk = |id: &[u8; 4], contents: Vec<u8>| {
+            buf.extend_from_slice(id);
+            buf.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&contents);
+            if contents.len() % 2 == 1 {
+                buf.push(0);
+            }
+        };
+
+        if !self.info.is_empty() {
+            push_chunk(b"LIST", self.list_chunk());
+        }
+        if !self.cues.is_empty() {
+            push_chunk(b"cue ", self.cue_chunk());
+        }
+        if let Some(sampler) = &self.sampler {
+            push_chunk(b"smpl", Self::smpl_chunk(sampler, sample_rate));
+        }
+        buf
+    }
+
+    /// Parses a `LIST` chunk. Lists other than `INFO` are ignored.
+    fn read_list(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.get(..4) != Some(b"INFO") {
+            return Ok(());
+        }
+
+        let mut pos = 4;
+        while pos < bytes.len() {
+            let id = array_at(bytes, pos).ok_or(Error::Truncated(*b"LIST"))?;
+            let size = u32_at(bytes, pos + 4).ok_or(Error::Truncated(*b"LIST"))? as usize;
+            let value = bytes
+                .get(pos + 8..pos + 8 + size)
+                .ok_or(Error::Truncated(*b"LIST"))?;
+
+            let value = String::from_utf8_lossy(value);
+            self.set_info(id, value.trim_end_matches('\0').to_owned());
+            pos += 8 + size + size % 2;
+        }
+        Ok(())
+    }
+
+    /// Parses a `cue ` chunk.
+    fn read_cue(&mut self, bytes: &[u8]) -> Result<()> {
+        let count = u32_at(bytes, 0).ok_or(Error::Truncated(*b"cue "))?;
+        for i in 0..count as usize {
+            let pos = 4 + 24 * i;
+            self.cues.push(Cue {
+                id: u32_at(bytes, pos).ok_or(Error::Truncated(*b"cue "))?,
+                // Uses the sample offset, which is the actual position for
+                // uncompressed data.
+                position: u32_at(bytes, pos + 20).ok_or(Error::Truncated(*b"cue "))?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Parses a `smpl` chunk.
+    fn read_smpl(&mut self, bytes: &[u8]) -> Result<()> {
+        let field = |pos| u32_at(bytes, pos).ok_or(Error::Truncated(*b"smpl"));
+        let mut sampler = Sampler {
+            unity_note: field(12)?,
+            pitch_fraction: field(16)?,
+            loops: Vec::new(),
+        };
+
+        let count = field(28)?;
+        for i in 0..count as usize {
+            let pos = 36 + 24 * i;
+            sampler.loops.push(SampleLoop {
+                id: field(pos)?,
+                kind: match field(pos + 4)? {
+                    1 => LoopKind::PingPong,
+                    2 => LoopKind::Backward,
+                    _ => LoopKind::Forward,
+                },
+                start: field(pos + 8)?,
+                end: field(pos + 12)?,
+                play_count: field(pos + 20)?,
+            });
+        }
+
+        self.sampler = Some(sampler);
+        Ok(())
+    }
 }
 
 /// Represents the raw bytes in an audio stream, in the same layout as the WAV
@@ -77,6 +674,12 @@ pub struct AudioData<T: AudioSample, const N: usize> {
 
     /// Number of samples per second.
     sample_rate: u32,
+
+    /// The speaker positions of the channels, as in the [`speaker`] module.
+    channel_mask: u32,
+
+    /// Text tags, markers and sampler settings.
+    metadata: Metadata,
 }
 
 impl<T: AudioSample, const N: usize> AudioData<T, N> {
@@ -85,9 +688,42 @@ impl<T: AudioSample, const N: usize> AudioData<T, N> {
         Self {
             data: Vec::new(),
             sample_rate,
+            channel_mask: speaker::default_mask(N),
+            metadata: Metadata::default(),
         }
     }
 
+    /// Number of samples per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The speaker positions of the channels, as in the [`speaker`] module.
+    pub fn channel_mask(&self) -> u32 {
+        self.channel_mask
+    }
+
+    /// Sets the speaker positions of the channels. A mask other than the
+    /// default for `N` channels forces an extensible `fmt ` chunk.
+    pub fn set_channel_mask(&mut self, channel_mask: u32) {
+        self.channel_mask = channel_mask
+    }
+
+    /// Text tags, markers and sampler settings.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Text tags, markers and sampler settings.
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    /// The samples in the audio stream, one array per frame.
+    pub fn data(&self) -> &[[T; N]] {
+        &self.data
+    }
+
     /// Resizes the buffer with new empty data.
     fn resize(&mut self, sample_count: u32) {
         if self.data.len() < sample_count as usize {
@@ -105,7 +741,6 @@ impl<T: AudioSample, const N: usize> AudioData<T, N> {
     ) {
         self.resize(init_sample);
         for i in init_sample..self.data.len() as u32 {
-            print!("Check 3");
             if let Some(t) = iter.next() {
                 f(&mut self.data[i as usize], t);
             } else {
@@ -113,13 +748,10 @@ impl<T: AudioSample, const N: usize> AudioData<T, N> {
             }
         }
 
-        print!("Check 1");
-
-        while let Some(t) = iter.next() {
+        for t in iter {
             self.data.push([T::ZERO; N]);
             f(self.data.last_mut().unwrap(), t);
         }
-        print!("Check 2");
     }
 
     /// Writes a raw data sample at the end of the audio data.
@@ -148,40 +780,941 @@ impl<T: AudioSample, const N: usize> AudioData<T, N> {
         })
     }
 
-    /// Number of bytes per second.
-    fn byte_rate(&self) -> u32 {
-        self.sample_rate * N as u32 * mem::size_of::<T>() as u32
+    /// Loads audio data from a WAV file.
+    ///
+    /// The file must have exactly `N` channels, and its samples must have the
+    /// same bit depth as `T`.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads audio data from a WAV stream, along with any metadata. Unknown
+    /// chunks are skipped. RF64 and BW64 streams are also supported.
+    ///
+    /// The stream must have exactly `N` channels, and its samples must have
+    /// the same bit depth as `T`.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let (rf64, riff_size) =
+            read_riff_header(&mut reader).map_err(|err| err.in_chunk(*b"RIFF"))?;
+
+        // In RF64 files, the 64-bit sizes come in a `ds64` chunk right after
+        // the header.
+        let (ds64, riff_size) = if rf64 {
+            match read_chunk_header(&mut reader)? {
+                Some((id, size)) if &id == b"ds64" => {
+                    let ds64 = Ds64::read(&mut reader, size).map_err(|err| err.in_chunk(id))?;
+                    let riff_size = ds64.riff_size.saturating_sub(8 + size as u64);
+                    (Some(ds64), riff_size)
+                }
+                _ => return Err(Error::MissingChunk(*b"ds64")),
+            }
+        } else {
+            (None, riff_size as u64)
+        };
+
+        // Only reads as far as the RIFF chunk says, in case there's trailing
+        // garbage after it.
+        let mut reader = reader.take(riff_size.saturating_sub(4));
+        let mut format = None;
+        let mut audio = None;
+        let mut metadata = Metadata::default();
+
+        while let Some((id, size)) = read_chunk_header(&mut reader)? {
+            let size = match &ds64 {
+                Some(ds64) if size == u32::MAX => ds64.chunk_size(id),
+                _ => size as u64,
+            };
+
+            Self::read_chunk_body(
+                &mut reader,
+                id,
+                size,
+                &mut format,
+                &mut audio,
+                &mut metadata,
+            )
+            .map_err(|err| err.in_chunk(id))?;
+        }
+
+        // The stream ended between two chunks, before the end of the RIFF
+        // chunk.
+        if reader.limit() > 0 {
+            return Err(Error::Truncated(*b"RIFF"));
+        }
+
+        let mut audio = audio.ok_or(Error::MissingChunk(*b"data"))?;
+        audio.metadata = metadata;
+        Ok(audio)
+    }
+
+    /// Reads the body of a chunk of the given size, including its pad byte,
+    /// and stores whatever it holds.
+    fn read_chunk_body<R: Read>(
+        reader: &mut io::Take<R>,
+        id: [u8; 4],
+        size: u64,
+        format: &mut Option<Format>,
+        audio: &mut Option<Self>,
+        metadata: &mut Metadata,
+    ) -> Result<()> {
+        match &id {
+            b"fmt " => {
+                let fmt = Format::read(reader, size as u32)?;
+                fmt.validate::<T, N>()?;
+                *format = Some(fmt);
+            }
+            b"data" => {
+                let fmt = format.as_ref().ok_or(Error::MissingChunk(*b"fmt "))?;
+                if size > reader.limit() {
+                    return Err(Error::Truncated(id));
+                }
+                let mut data = Self::new(fmt.sample_rate);
+                data.channel_mask = fmt.channel_mask;
+                data.read_samples(reader, size)?;
+                *audio = Some(data);
+            }
+            b"LIST" => metadata.read_list(&read_chunk(reader, size)?)?,
+            b"cue " => metadata.read_cue(&read_chunk(reader, size)?)?,
+            b"smpl" => metadata.read_smpl(&read_chunk(reader, size)?)?,
+            _ => skip(reader, size)?,
+        }
+
+        // Chunks are padded to an even number of bytes.
+        if size % 2 == 1 {
+            skip(reader, 1)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes the contents of a `data` chunk of the given size.
+    fn read_samples<R: Read>(&mut self, reader: &mut R, size: u64) -> io::Result<()> {
+        const BATCH_FRAMES: usize = 4096;
+
+        let sample_size = T::BYTES;
+        let frame_size = self.block_align();
+        let mut frames = (size / frame_size as u64) as usize;
+        let mut buf = vec![0; BATCH_FRAMES * frame_size];
+        // The size comes from the header, so it can't be trusted to allocate
+        // everything up front. The data grows as batches are actually read.
+        self.data.reserve(frames.min(BATCH_FRAMES));
+
+        while frames > 0 {
+            let batch = frames.min(BATCH_FRAMES);
+            let bytes = &mut buf[..batch * frame_size];
+            reader.read_exact(bytes)?;
+
+            for frame in bytes.chunks_exact(frame_size) {
+                let mut sample = [T::ZERO; N];
+                for (channel, bytes) in sample.iter_mut().zip(frame.chunks_exact(sample_size)) {
+                    *channel = T::from_le_bytes(bytes);
+                }
+                self.data.push(sample);
+            }
+
+            frames -= batch;
+        }
+
+        // Skips any trailing partial frame.
+        skip(reader, size % frame_size as u64)
     }
 
     /// Number of bytes per sample.
-    fn block_align(&self) -> u16 {
-        N as u16 * mem::size_of::<T>() as u16
-    }
-
-    /// Saves the audio data to a WAV file.
-    pub fn save_to(&self, path: &Path) -> io::Result<()> {
-        let size = self.data.len() as u32 * mem::size_of::<T>() as u32 * N as u32;
-        let mut file = File::create(path)?;
-
-        // Writes header.
-        file.write_all(b"RIFF")?;
-        file.write_all(&(36 + size).to_le_bytes())?;
-        file.write_all(b"WAVEfmt ")?;
-        file.write_all(&[16, 0, 0, 0, 1, 0])?;
-        file.write_all(&(N as u16).to_le_bytes())?;
-        file.write_all(&self.sample_rate.to_le_bytes())?;
-        file.write_all(&self.byte_rate().to_le_bytes())?;
-        file.write_all(&self.block_align().to_le_bytes())?;
-        file.write_all(&(mem::size_of::<T>() as u16 * 8).to_le_bytes())?;
-        file.write_all(b"data")?;
-        file.write_all(&size.to_le_bytes())?;
+    fn block_align(&self) -> usize {
+        N * T::BYTES
+    }
+
+    /// The `fmt ` chunk describing this data.
+    fn format(&self) -> Result<Format> {
+        Format::new::<T, N>(self.sample_rate, self.channel_mask)
+    }
+
+    /// Saves the audio data to a WAV file. See [`AudioData::write_to`] for
+    /// details on the format.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        self.write_to(File::create(path)?)
+    }
+
+    /// Writes the audio data as a WAV stream.
+    ///
+    /// Any metadata is written after the audio. Files larger than 4 GiB are
+    /// written in the RF64 format. Floating point samples are written in the
+    /// IEEE float format, which also requires a `fact` chunk. Files with more
+    /// than two channels, more than 16 bits per integer sample, or a
+    /// non-default channel mask use the extensible format.
+    ///
+    /// Samples are encoded in batches, so there's no need to wrap the writer
+    /// in a [`BufWriter`](io::BufWriter).
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        const BATCH_FRAMES: usize = 4096;
+
+        let metadata = self.metadata.to_chunks(self.sample_rate);
+        let mut buf = Vec::with_capacity(BATCH_FRAMES * self.block_align());
+        self.format()?.write_header(
+            &mut buf,
+            self.data.len() as u64,
+            metadata.len() as u64,
+            false,
+        )?;
 
         // Writes the audio data.
-        for sample in &self.data {
-            for channel in sample {
-                file.write_all(&channel.to_le_bytes())?;
+        for frames in self.data.chunks(BATCH_FRAMES) {
+            for frame in frames {
+                encode_frame(frame, &mut buf);
             }
+            writer.write_all(&buf)?;
+            buf.clear();
         }
+
+        // Chunks are padded to an even number of bytes.
+        if self.data.len() * self.block_align() % 2 == 1 {
+            buf.push(0);
+        }
+        writer.write_all(&buf)?;
+        writer.write_all(&metadata)?;
+        writer.flush()?;
         Ok(())
     }
 }
+
+/// Appends the little endian representation of a frame to a buffer.
+fn encode_frame<T: AudioSample, const N: usize>(frame: &[T; N], buf: &mut Vec<u8>) {
+    for channel in frame {
+        buf.extend_from_slice(&channel.to_le_bytes());
+    }
+}
+
+/// Writes a WAV file one frame at a time, so that renders of any length can
+/// be written with constant memory.
+///
+/// The header is written up front with placeholder sizes, which are patched
+/// when the writer is finalized or dropped. Space is reserved for a `ds64`
+/// chunk, so the file becomes RF64 if it turns out to be larger than 4 GiB.
+/// Errors while dropping are ignored, so call [`WavWriter::finalize`] to
+/// handle them.
+pub struct WavWriter<W: Write + Seek, T: AudioSample, const N: usize> {
+    /// The underlying writer. This is only `None` after finalizing.
+    writer: Option<W>,
+
+    /// The format of the file being written.
+    format: Format,
+
+    /// The stream position where the file starts.
+    start: u64,
+
+    /// Number of frames written so far.
+    frames: u64,
+
+    /// Encoded frames that haven't been written yet.
+    buf: Vec<u8>,
+
+    /// Metadata to write after the audio.
+    metadata: Metadata,
+
+    _phantom: PhantomData<T>,
+}
+
+impl<W: Write + Seek, T: AudioSample, const N: usize> WavWriter<W, T, N> {
+    /// How many frames are encoded before they're sent to the writer.
+    const BATCH_FRAMES: usize = 4096;
+
+    /// Starts a WAV file at the current position of the writer, with a custom
+    /// channel mask.
+    pub fn new_with_channel_mask(
+        mut writer: W,
+        sample_rate: u32,
+        channel_mask: u32,
+    ) -> Result<Self> {
+        let format = Format::new::<T, N>(sample_rate, channel_mask)?;
+        let start = writer.stream_position()?;
+        format.write_header(&mut writer, 0, 0, true)?;
+
+        Ok(Self {
+            writer: Some(writer),
+            buf: Vec::with_capacity(Self::BATCH_FRAMES * format.block_align as usize),
+            format,
+            start,
+            frames: 0,
+            metadata: Metadata::default(),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Starts a WAV file at the current position of the writer.
+    pub fn new(writer: W, sample_rate: u32) -> Result<Self> {
+        Self::new_with_channel_mask(writer, sample_rate, speaker::default_mask(N))
+    }
+
+    /// Number of frames written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Metadata to write after the audio, when the file is finalized.
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    /// Writes a single frame.
+    pub fn write_frame(&mut self, frame: [T; N]) -> Result<()> {
+        encode_frame(&frame, &mut self.buf);
+        self.frames += 1;
+
+        if self.buf.len() >= self.buf.capacity() {
+            self.flush_buf()?;
+        }
+        Ok(())
+    }
+
+    /// Writes every frame from an iterator, such as an
+    /// [`InstrumentIter`](crate::basic::InstrumentIter). Make sure the
+    /// iterator is finite!
+    pub fn write_frames<I: IntoIterator<Item = [T; N]>>(&mut self, iter: I) -> Result<()> {
+        for frame in iter {
+            self.write_frame(frame)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the encoded frames to the writer.
+    fn flush_buf(&mut self) -> io::Result<()> {
+        if let Some(writer) = &mut self.writer {
+            writer.write_all(&self.buf)?;
+        }
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// Writes any pending frames and patches the header sizes, leaving the
+    /// writer at the end of the file.
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let size = self.frames * self.format.block_align as u64;
+        if size % 2 == 1 {
+            writer.write_all(&[0])?;
+        }
+
+        let metadata = self.metadata.to_chunks(self.format.sample_rate);
+        writer.write_all(&metadata)?;
+
+        let end = writer.stream_position()?;
+        writer.seek(SeekFrom::Start(self.start))?;
+        self.format
+            .write_header(writer, self.frames, metadata.len() as u64, true)?;
+        writer.seek(SeekFrom::Start(end))?;
+        writer.flush()
+    }
+
+    /// Finishes the file and returns the underlying writer.
+    pub fn finalize(mut self) -> Result<W> {
+        let result = self.finish();
+        // The writer is taken out even on failure, so that dropping `self`
+        // doesn't write the trailer a second time.
+        let writer = self.writer.take();
+        result?;
+        Ok(writer.unwrap())
+    }
+}
+
+impl<W: Write + Seek, T: AudioSample, const N: usize> Drop for WavWriter<W, T, N> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// The contents of a `fmt ` chunk.
+///
+/// For extensible chunks, the format tag is the one from the subformat GUID.
+struct Format {
+    format_tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    block_align: u16,
+    bits_per_sample: u16,
+    valid_bits: u16,
+    channel_mask: u32,
+}
+
+impl Format {
+    /// The format for samples of type `T` in `N` channels.
+    ///
+    /// Fails if the block alignment or the byte rate don't fit in the header.
+    fn new<T: AudioSample, const N: usize>(sample_rate: u32, channel_mask: u32) -> Result<Self> {
+        let block_align = u16::try_from(N * T::BYTES).map_err(|_| Error::SizeOverflow)?;
+        sample_rate
+            .checked_mul(block_align as u32)
+            .ok_or(Error::SizeOverflow)?;
+
+        Ok(Self {
+            format_tag: T::FORMAT_TAG,
+            channels: N as u16,
+            sample_rate,
+            block_align,
+            bits_per_sample: T::BITS,
+            valid_bits: T::BITS,
+            channel_mask,
+        })
+    }
+
+    /// Whether the file needs a `fact` chunk, which is the case for every
+    /// format other than PCM.
+    fn has_fact(&self) -> bool {
+        self.format_tag != WAVE_FORMAT_PCM
+    }
+
+    /// The number of bytes from the start of the file to the start of the
+    /// audio data.
+    ///
+    /// If `reserve_ds64` is set, this includes room for a `ds64` chunk.
+    fn header_size(&self, reserve_ds64: bool) -> u64 {
+        let ds64_size = if reserve_ds64 { 8 + DS64_SIZE } else { 0 };
+        let fact_size = if self.has_fact() { 12 } else { 0 };
+        12 + ds64_size + 8 + self.size() as u64 + fact_size + 8
+    }
+
+    /// Writes every chunk up to the audio data, for a file with the given
+    /// number of frames and `trailer` bytes of chunks after the audio.
+    ///
+    /// If the file doesn't fit the 32-bit sizes of a RIFF file, this writes an
+    /// RF64 header instead, with the actual sizes in a `ds64` chunk. If
+    /// `reserve_ds64` is set, the header has the same size either way, as a
+    /// `JUNK` chunk takes up the space of the `ds64` chunk when it's unused.
+    fn write_header<W: Write>(
+        &self,
+        writer: &mut W,
+        frames: u64,
+        trailer: u64,
+        reserve_ds64: bool,
+    ) -> io::Result<()> {
+        let size = frames * self.block_align as u64;
+        let riff_size = self.header_size(reserve_ds64) - 8 + size + size % 2 + trailer;
+        let rf64 = riff_size > u32::MAX as u64;
+        let riff_size = if rf64 {
+            self.header_size(true) - 8 + size + size % 2 + trailer
+        } else {
+            riff_size
+        };
+
+        // Writes a placeholder if the actual size is in the `ds64` chunk.
+        let size32 = |size: u64| if rf64 { u32::MAX } else { size as u32 };
+
+        if rf64 {
+            writer.write_all(b"RF64")?;
+            writer.write_all(&u32::MAX.to_le_bytes())?;
+            writer.write_all(b"WAVEds64")?;
+            writer.write_all(&(DS64_SIZE as u32).to_le_bytes())?;
+            writer.write_all(&riff_size.to_le_bytes())?;
+            writer.write_all(&size.to_le_bytes())?;
+            writer.write_all(&frames.to_le_bytes())?;
+            // No other chunks need a 64-bit size.
+            writer.write_all(&0u32.to_le_bytes())?;
+        } else {
+            writer.write_all(b"RIFF")?;
+            writer.write_all(&(riff_size as u32).to_le_bytes())?;
+            writer.write_all(b"WAVE")?;
+            if reserve_ds64 {
+                writer.write_all(b"JUNK")?;
+                writer.write_all(&(DS64_SIZE as u32).to_le_bytes())?;
+                writer.write_all(&[0; DS64_SIZE as usize])?;
+            }
+        }
+
+        self.write(writer)?;
+        if self.has_fact() {
+            writer.write_all(b"fact")?;
+            writer.write_all(&4u32.to_le_bytes())?;
+            writer.write_all(&size32(frames).to_le_bytes())?;
+        }
+        writer.write_all(b"data")?;
+        writer.write_all(&size32(size).to_le_bytes())
+    }
+
+    /// Whether this format can't be described by a plain `fmt ` chunk.
+    fn is_extensible(&self) -> bool {
+        self.channels > 2
+            || (self.format_tag == WAVE_FORMAT_PCM && self.bits_per_sample > 16)
+            || self.valid_bits != self.bits_per_sample
+            || self.channel_mask != speaker::default_mask(self.channels as usize)
+    }
+
+    /// The size of the chunk's contents.
+    fn size(&self) -> u32 {
+        if self.is_extensible() {
+            40
+        } else if self.format_tag == WAVE_FORMAT_PCM {
+            16
+        } else {
+            // Non-PCM formats have an extra (empty) extension size field.
+            18
+        }
+    }
+
+    /// Writes the chunk, including its header.
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let extensible = self.is_extensible();
+        let format_tag = if extensible {
+            WAVE_FORMAT_EXTENSIBLE
+        } else {
+            self.format_tag
+        };
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&self.size().to_le_bytes())?;
+        writer.write_all(&format_tag.to_le_bytes())?;
+        writer.write_all(&self.channels.to_le_bytes())?;
+        writer.write_all(&self.sample_rate.to_le_bytes())?;
+        writer.write_all(&(self.sample_rate * self.block_align as u32).to_le_bytes())?;
+        writer.write_all(&self.block_align.to_le_bytes())?;
+        writer.write_all(&self.bits_per_sample.to_le_bytes())?;
+
+        if extensible {
+            writer.write_all(&22u16.to_le_bytes())?;
+            writer.write_all(&self.valid_bits.to_le_bytes())?;
+            writer.write_all(&self.channel_mask.to_le_bytes())?;
+            writer.write_all(&self.format_tag.to_le_bytes())?;
+            writer.write_all(&SUBTYPE_GUID_SUFFIX)?;
+        } else if self.format_tag != WAVE_FORMAT_PCM {
+            writer.write_all(&0u16.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a `fmt ` chunk of the given size. Any extension bytes we don't
+    /// use are skipped.
+    fn read<R: Read>(reader: &mut R, size: u32) -> Result<Self> {
+        if size < 16 {
+            return Err(Error::Truncated(*b"fmt "));
+        }
+
+        let mut format = Self {
+            format_tag: read_u16(reader)?,
+            channels: read_u16(reader)?,
+            sample_rate: read_u32(reader)?,
+            block_align: {
+                // Skips the byte rate, which is redundant.
+                read_u32(reader)?;
+                read_u16(reader)?
+            },
+            bits_per_sample: read_u16(reader)?,
+            valid_bits: 0,
+            channel_mask: 0,
+        };
+        format.valid_bits = format.bits_per_sample;
+        format.channel_mask = speaker::default_mask(format.channels as usize);
+        let mut read = 16;
+
+        if format.format_tag == WAVE_FORMAT_EXTENSIBLE {
+            if size < 40 {
+                return Err(Error::Truncated(*b"fmt "));
+            }
+
+            // Skips the extension size.
+            read_u16(reader)?;
+            format.valid_bits = read_u16(reader)?;
+            format.channel_mask = read_u32(reader)?;
+            format.format_tag = read_u16(reader)?;
+
+            let mut suffix = [0; 14];
+            reader.read_exact(&mut suffix)?;
+            if suffix != SUBTYPE_GUID_SUFFIX {
+                return Err(Error::UnsupportedFormat(WAVE_FORMAT_EXTENSIBLE));
+            }
+            read = 40;
+        }
+
+        skip(reader, (size - read) as u64)?;
+        Ok(format)
+    }
+
+    /// Checks that the format can be decoded into `AudioData<T, N>`.
+    fn validate<T: AudioSample, const N: usize>(&self) -> Result<()> {
+        match self.format_tag {
+            tag if tag == T::FORMAT_TAG => {}
+            WAVE_FORMAT_PCM | WAVE_FORMAT_IEEE_FLOAT => {
+                return Err(Error::FormatMismatch {
+                    expected: T::FORMAT_TAG,
+                    found: self.format_tag,
+                })
+            }
+            tag => return Err(Error::UnsupportedFormat(tag)),
+        }
+
+        let expected = u16::try_from(N).map_err(|_| Error::SizeOverflow)?;
+        if self.channels != expected {
+            return Err(Error::ChannelMismatch {
+                expected,
+                found: self.channels,
+            });
+        }
+
+        let expected = T::BITS;
+        if self.bits_per_sample != expected {
+            return Err(Error::BitDepthMismatch {
+                expected,
+                found: self.bits_per_sample,
+            });
+        }
+
+        let expected = u16::try_from(N * T::BYTES).map_err(|_| Error::SizeOverflow)?;
+        if self.block_align != expected {
+            return Err(Error::BlockAlignMismatch {
+                expected,
+                found: self.block_align,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// The contents of a `ds64` chunk, which holds the sizes that don't fit in 32
+/// bits in an RF64 file.
+struct Ds64 {
+    riff_size: u64,
+    data_size: u64,
+
+    /// The sizes of any other large chunks.
+    table: Vec<([u8; 4], u64)>,
+}
+
+impl Ds64 {
+    /// Parses a `ds64` chunk of the given size.
+    fn read<R: Read>(reader: &mut R, size: u32) -> Result<Self> {
+        if (size as u64) < DS64_SIZE {
+            return Err(Error::Truncated(*b"ds64"));
+        }
+
+        let riff_size = read_u64(reader)?;
+        let data_size = read_u64(reader)?;
+        // Skips the sample count, which is also in the `fact` chunk.
+        read_u64(reader)?;
+
+        let table_len = read_u32(reader)?;
+        let mut table = Vec::new();
+        let mut read = DS64_SIZE;
+        for _ in 0..table_len {
+            if read + 12 > size as u64 {
+                return Err(Error::Truncated(*b"ds64"));
+            }
+
+            let mut id = [0; 4];
+            reader.read_exact(&mut id)?;
+            table.push((id, read_u64(reader)?));
+            read += 12;
+        }
+
+        skip(reader, size as u64 - read)?;
+        Ok(Self {
+            riff_size,
+            data_size,
+            table,
+        })
+    }
+
+    /// The actual size of a chunk whose header has the placeholder size.
+    fn chunk_size(&self, id: [u8; 4]) -> u64 {
+        if &id == b"data" {
+            return self.data_size;
+        }
+
+        self.table
+            .iter()
+            .find(|(chunk, _)| *chunk == id)
+            .map_or(u32::MAX as u64, |&(_, size)| size)
+    }
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Reads a little endian `u32` from a byte slice.
+fn u32_at(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(array_at(bytes, pos)?))
+}
+
+/// Reads four bytes from a byte slice.
+fn array_at(bytes: &[u8], pos: usize) -> Option<[u8; 4]> {
+    bytes.get(pos..pos + 4)?.try_into().ok()
+}
+
+/// Reads the contents of a chunk into memory.
+fn read_chunk<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(size).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+/// Discards the next `len` bytes of the reader.
+fn skip<R: Read>(reader: &mut R, len: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(len), &mut io::sink())?;
+    if skipped < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Reads the header of a WAV stream. Returns whether it's an RF64 stream,
+/// along with the size of the RIFF chunk.
+fn read_riff_header<R: Read>(reader: &mut R) -> Result<(bool, u32)> {
+    let mut id = [0; 4];
+    reader.read_exact(&mut id)?;
+    let rf64 = match &id {
+        b"RIFF" => false,
+        b"RF64" | b"BW64" => true,
+        _ => return Err(Error::NotWave),
+    };
+
+    let riff_size = read_u32(reader)?;
+    reader.read_exact(&mut id)?;
+    if &id != b"WAVE" {
+        return Err(Error::NotWave);
+    }
+    Ok((rf64, riff_size))
+}
+
+/// Reads the ID and size of the next chunk, or returns `None` if the reader is
+/// exhausted.
+fn read_chunk_header<R: Read>(reader: &mut R) -> io::Result<Option<([u8; 4], u32)>> {
+    let mut id = [0; 4];
+    let mut read = 0;
+    while read < id.len() {
+        match reader.read(&mut id[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(Some((id, read_u32(reader)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Some frames of a stereo sweep, different on each channel.
+    fn frames<T: AudioSample>(len: usize) -> Vec<[T; 2]> {
+        (0..len)
+            .map(|i| {
+                let x = (i as f64 * i as f64 / 500.0).sin();
+                [T::from_f64(x), T::from_f64(-x / 2.0)]
+            })
+            .collect()
+    }
+
+    /// Metadata using every kind of chunk.
+    fn metadata() -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.set_info(*b"INAM", "Sweep".to_owned());
+        metadata.set_info(*b"ICMT", "Odd length".to_owned());
+        metadata.cues.push(Cue { id: 1, position: 7 });
+        metadata.cues.push(Cue {
+            id: 2,
+            position: 20,
+        });
+        metadata.sampler = Some(Sampler {
+            unity_note: 60,
+            pitch_fraction: 1 << 31,
+            loops: vec![SampleLoop {
+                id: 3,
+                kind: LoopKind::PingPong,
+                start: 5,
+                end: 30,
+                play_count: 0,
+            }],
+        });
+        metadata
+    }
+
+    /// Writes audio with [`AudioData::write_to`].
+    fn encode<T: AudioSample>(frames: &[[T; 2]], metadata: Metadata) -> Vec<u8> {
+        let mut audio = AudioData::<T, 2>::new(8000);
+        audio.extend_data(frames.iter().copied());
+        *audio.metadata_mut() = metadata;
+
+        let mut bytes = Vec::new();
+        audio.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Writes audio with a [`WavWriter`].
+    fn stream<T: AudioSample>(frames: &[[T; 2]], metadata: Metadata) -> Vec<u8> {
+        let mut writer = WavWriter::<_, T, 2>::new(Cursor::new(Vec::new()), 8000).unwrap();
+        writer.write_frames(frames.iter().copied()).unwrap();
+        *writer.metadata_mut() = metadata;
+        writer.finalize().unwrap().into_inner()
+    }
+
+    /// Checks that both writers produce files that read back unchanged.
+    fn round_trip<T: AudioSample + PartialEq>() {
+        // An odd number of frames, so 8-bit audio needs a pad byte.
+        let frames = frames::<T>(101);
+        for bytes in [encode(&frames, metadata()), stream(&frames, metadata())] {
+            let audio = AudioData::<T, 2>::read_from(bytes.as_slice()).unwrap();
+            assert_eq!(audio.sample_rate(), 8000);
+            assert_eq!(audio.channel_mask(), speaker::STEREO);
+            assert_eq!(audio.data(), frames.as_slice());
+            assert_eq!(audio.metadata(), &metadata());
+        }
+    }
+
+    #[test]
+    fn round_trip_u8() {
+        round_trip::<u8>();
+    }
+
+    #[test]
+    fn round_trip_i16() {
+        round_trip::<i16>();
+    }
+
+    #[test]
+    fn round_trip_i24() {
+        round_trip::<I24>();
+    }
+
+    #[test]
+    fn round_trip_f32() {
+        round_trip::<f32>();
+    }
+
+    #[test]
+    fn round_trip_f64() {
+        round_trip::<f64>();
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let frames = frames::<i16>(25);
+        for bytes in [encode(&frames, metadata()), stream(&frames, metadata())] {
+            for len in 0..bytes.len() {
+                assert!(
+                    AudioData::<i16, 2>::read_from(&bytes[..len]).is_err(),
+                    "read a file truncated to {} of {} bytes",
+                    len,
+                    bytes.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn read_rf64() {
+        let samples: [i16; 4] = [0, 1000, -1000, i16::MAX];
+        let data: Vec<u8> = samples.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let riff_size = 4 + (8 + DS64_SIZE) + (8 + 16) + (8 + data.len() as u64);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RF64");
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(b"WAVEds64");
+        bytes.extend_from_slice(&(DS64_SIZE as u32).to_le_bytes());
+        bytes.extend_from_slice(&riff_size.to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(samples.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&8000u32.to_le_bytes());
+        bytes.extend_from_slice(&16000u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&data);
+
+        let audio = AudioData::<i16, 1>::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(audio.sample_rate(), 8000);
+        assert_eq!(audio.data(), samples.map(|x| [x]).as_slice());
+    }
+
+    #[test]
+    fn mismatches_are_rejected() {
+        let bytes = encode(&frames::<i16>(10), Metadata::default());
+
+        let err = AudioData::<i16, 1>::read_from(bytes.as_slice()).err();
+        assert!(
+            matches!(
+                err,
+                Some(Error::ChannelMismatch {
+                    expected: 1,
+                    found: 2
+                })
+            ),
+            "{:?}",
+            err
+        );
+
+        let err = AudioData::<I24, 2>::read_from(bytes.as_slice()).err();
+        assert!(
+            matches!(
+                err,
+                Some(Error::BitDepthMismatch {
+                    expected: 24,
+                    found: 16
+                })
+            ),
+            "{:?}",
+            err
+        );
+
+        let err = AudioData::<f32, 2>::read_from(bytes.as_slice()).err();
+        assert!(
+            matches!(
+                err,
+                Some(Error::FormatMismatch {
+                    expected: WAVE_FORMAT_IEEE_FLOAT,
+                    found: WAVE_FORMAT_PCM
+                })
+            ),
+            "{:?}",
+            err
+        );
+
+        // Patches the block alignment of the plain `fmt ` chunk.
+        let mut bytes = bytes;
+        bytes[32..34].copy_from_slice(&6u16.to_le_bytes());
+        let err = AudioData::<i16, 2>::read_from(bytes.as_slice()).err();
+        assert!(
+            matches!(
+                err,
+                Some(Error::BlockAlignMismatch {
+                    expected: 4,
+                    found: 6
+                })
+            ),
+            "{:?}",
+            err
+        );
+    }
+}